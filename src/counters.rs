@@ -0,0 +1,44 @@
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard, OnceLock};
+
+// Each counter keeps the uuid of the Ray entry it was first sent to, so later calls update that
+// entry in place instead of stacking new ones.
+#[derive(Debug, Clone)]
+pub struct RayCounter {
+    pub times: u32,
+    pub uuid: String,
+}
+
+static COUNTERS: OnceLock<Mutex<HashMap<String, RayCounter>>> = OnceLock::new();
+
+fn counters() -> MutexGuard<'static, HashMap<String, RayCounter>> {
+    COUNTERS
+        .get_or_init(|| Mutex::new(HashMap::new()))
+        .lock()
+        // A panic while holding the lock can't leave a counter half updated, so just carry on
+        .unwrap_or_else(|error| error.into_inner())
+}
+
+pub fn increment(name: &str) -> RayCounter {
+    let mut counters = counters();
+
+    let counter = counters.entry(name.to_string()).or_insert(RayCounter {
+        times: 0,
+        uuid: uuid::Uuid::new_v4().to_string(),
+    });
+
+    counter.times += 1;
+
+    counter.clone()
+}
+
+pub fn value(name: &str) -> u32 {
+    counters()
+        .get(name)
+        .map(|counter| counter.times)
+        .unwrap_or(0)
+}
+
+pub fn clear() {
+    counters().clear();
+}
@@ -0,0 +1,583 @@
+// Turns the output of Rust's pretty Debug formatter (`{:#?}`) into a collapsible HTML tree,
+// similar to what Symfony's VarDumper gives the PHP client.
+//
+// Debug output isn't a real format, anyone can write a custom impl, so if we can't make sense of
+// the string we just fall back to an escaped <pre> block.
+
+// Anything nested deeper than this starts collapsed
+const OPEN_DEPTH: usize = 2;
+
+pub(crate) const STYLE_ROOT: &str = "font-family: monospace; font-size: 0.875em; line-height: 1.5;";
+const STYLE_CHILDREN: &str = "padding-left: 1.5em; border-left: 1px dotted #d1d5db;";
+const STYLE_SUMMARY: &str = "cursor: pointer;";
+const STYLE_TYPE: &str = "color: #7c3aed; font-weight: bold;";
+const STYLE_KEY: &str = "color: #0f172a;";
+const STYLE_STRING: &str = "color: #16a34a;";
+const STYLE_NUMBER: &str = "color: #2563eb;";
+const STYLE_CONST: &str = "color: #d97706;";
+pub(crate) const STYLE_MUTED: &str = "color: #9ca3af;";
+pub(crate) const STYLE_CAPTION: &str = "color: #6b7280; font-size: 0.75em;";
+
+// Controls how much of a dumped value is shown
+#[derive(Debug, Clone, Copy)]
+pub struct DumpOptions {
+    // Containers nested deeper than this start collapsed
+    pub expand_depth: usize,
+    // Containers nested deeper than this aren't rendered at all, only summarised
+    pub max_depth: Option<usize>,
+}
+
+impl Default for DumpOptions {
+    fn default() -> Self {
+        Self {
+            expand_depth: OPEN_DEPTH,
+            max_depth: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DebugNode {
+    Null,
+    Str(String),
+    Char(String),
+    Number(String),
+    Bool(String),
+    // Unit structs, unit enum variants and anything else we don't recognise
+    Atom(String),
+    Struct {
+        name: String,
+        fields: Vec<(String, DebugNode)>,
+        non_exhaustive: bool,
+    },
+    // Tuple structs, tuple enum variants and plain tuples (no name)
+    Tuple {
+        name: Option<String>,
+        items: Vec<DebugNode>,
+    },
+    Seq(Vec<DebugNode>),
+    Map(Vec<(DebugNode, DebugNode)>),
+    Set(Vec<DebugNode>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Str(String),
+    Char(String),
+    Atom(String),
+    Open(char),
+    Close(char),
+    Colon,
+    Comma,
+}
+
+// Renders a `{:#?}` string as a collapsible HTML tree, falling back to an escaped `<pre>` block
+// when the string can't be parsed
+pub fn debug_to_html(debug: &str) -> String {
+    match parse(debug) {
+        Some(node) => node_to_html(&node, DumpOptions::default()),
+        None => pre_to_html(debug),
+    }
+}
+
+pub(crate) fn node_to_html(node: &DebugNode, options: DumpOptions) -> String {
+    let mut html = format!("<div style=\"{}\">", STYLE_ROOT);
+    render(node, 0, &options, &mut html);
+    html.push_str("</div>");
+
+    html
+}
+
+pub(crate) fn pre_to_html(value: &str) -> String {
+    format!("<pre style=\"{}\">{}</pre>", STYLE_ROOT, escape_html(value))
+}
+
+// Parses Debug output (pretty or compact) into a tree of nodes
+pub fn parse(debug: &str) -> Option<DebugNode> {
+    let tokens = tokenize(debug)?;
+    let mut parser = Parser { tokens, pos: 0 };
+
+    let node = parser.value()?;
+
+    // Anything left over means we misunderstood the input
+    if parser.pos != parser.tokens.len() {
+        return None;
+    }
+
+    Some(node)
+}
+
+// Points at a line and column of some source text, used to report parse errors
+pub(crate) fn error_snippet_html(
+    title: &str,
+    source: &str,
+    line: usize,
+    column: usize,
+    message: &str,
+) -> String {
+    let text = source
+        .lines()
+        .nth(line.saturating_sub(1))
+        .unwrap_or_default();
+    let caret = format!("{}^", " ".repeat(column.saturating_sub(1)));
+
+    format!(
+        "<div style=\"color: #dc2626;\">{} at line {}, column {}: {}</div><pre>{}\n{}</pre>",
+        escape_html(title),
+        line,
+        column,
+        escape_html(message),
+        escape_html(text),
+        caret
+    )
+}
+
+pub(crate) fn escape_html(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+fn tokenize(input: &str) -> Option<Vec<Token>> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+
+        match c {
+            c if c.is_whitespace() => i += 1,
+            '{' | '(' | '[' => {
+                tokens.push(Token::Open(c));
+                i += 1;
+            }
+            '}' | ')' | ']' => {
+                tokens.push(Token::Close(c));
+                i += 1;
+            }
+            ',' => {
+                tokens.push(Token::Comma);
+                i += 1;
+            }
+            // `::` is part of a path, a single colon separates a key from its value
+            ':' if chars.get(i + 1) != Some(&':') => {
+                tokens.push(Token::Colon);
+                i += 1;
+            }
+            '"' | '\'' => {
+                let start = i;
+                i += 1;
+
+                while i < chars.len() && chars[i] != c {
+                    if chars[i] == '\\' {
+                        i += 1;
+                    }
+                    i += 1;
+                }
+
+                if i >= chars.len() {
+                    return None;
+                }
+
+                i += 1;
+
+                let literal: String = chars[start..i].iter().collect();
+
+                if c == '"' {
+                    tokens.push(Token::Str(literal));
+                } else {
+                    tokens.push(Token::Char(literal));
+                }
+            }
+            _ => {
+                let start = i;
+
+                while i < chars.len() {
+                    let c = chars[i];
+
+                    if c == ':' && chars.get(i + 1) == Some(&':') {
+                        i += 2;
+                        continue;
+                    }
+
+                    if c.is_whitespace() || "{}()[],:\"'".contains(c) {
+                        break;
+                    }
+
+                    i += 1;
+                }
+
+                tokens.push(Token::Atom(chars[start..i].iter().collect()));
+            }
+        }
+    }
+
+    Some(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+
+        token
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        if self.peek() == Some(token) {
+            self.pos += 1;
+            return true;
+        }
+
+        false
+    }
+
+    fn value(&mut self) -> Option<DebugNode> {
+        match self.next()? {
+            Token::Str(value) => Some(DebugNode::Str(value)),
+            Token::Char(value) => Some(DebugNode::Char(value)),
+            Token::Open('[') => Some(DebugNode::Seq(self.items(']')?)),
+            Token::Open('(') => Some(DebugNode::Tuple {
+                name: None,
+                items: self.items(')')?,
+            }),
+            Token::Open('{') => self.map_or_set(),
+            Token::Atom(name) => match self.peek() {
+                Some(Token::Open('{')) => {
+                    self.pos += 1;
+                    self.fields(name)
+                }
+                Some(Token::Open('(')) => {
+                    self.pos += 1;
+                    Some(DebugNode::Tuple {
+                        name: Some(name),
+                        items: self.items(')')?,
+                    })
+                }
+                _ => Some(classify(name)),
+            },
+            _ => None,
+        }
+    }
+
+    // Comma separated values up to the closing delimiter, trailing comma allowed
+    fn items(&mut self, close: char) -> Option<Vec<DebugNode>> {
+        let mut items = Vec::new();
+
+        loop {
+            if self.eat(&Token::Close(close)) {
+                return Some(items);
+            }
+
+            items.push(self.value()?);
+
+            if !self.eat(&Token::Comma) {
+                return self.eat(&Token::Close(close)).then_some(items);
+            }
+        }
+    }
+
+    fn fields(&mut self, name: String) -> Option<DebugNode> {
+        let mut fields = Vec::new();
+        let mut non_exhaustive = false;
+
+        loop {
+            if self.eat(&Token::Close('}')) {
+                break;
+            }
+
+            let field = match self.next()? {
+                Token::Atom(field) => field,
+                _ => return None,
+            };
+
+            if field == ".." {
+                non_exhaustive = true;
+            } else {
+                if !self.eat(&Token::Colon) {
+                    return None;
+                }
+
+                fields.push((field, self.value()?));
+            }
+
+            if !self.eat(&Token::Comma) {
+                if !self.eat(&Token::Close('}')) {
+                    return None;
+                }
+                break;
+            }
+        }
+
+        Some(DebugNode::Struct {
+            name,
+            fields,
+            non_exhaustive,
+        })
+    }
+
+    // A bare `{` is either a map (`{k: v}`) or a set (`{v}`), the first entry tells us which
+    fn map_or_set(&mut self) -> Option<DebugNode> {
+        if self.eat(&Token::Close('}')) {
+            return Some(DebugNode::Map(vec![]));
+        }
+
+        let first = self.value()?;
+
+        if !self.eat(&Token::Colon) {
+            let mut items = vec![first];
+
+            if self.eat(&Token::Comma) {
+                items.extend(self.items('}')?);
+            } else if !self.eat(&Token::Close('}')) {
+                return None;
+            }
+
+            return Some(DebugNode::Set(items));
+        }
+
+        let mut entries = vec![(first, self.value()?)];
+
+        loop {
+            if !self.eat(&Token::Comma) {
+                return self
+                    .eat(&Token::Close('}'))
+                    .then_some(DebugNode::Map(entries));
+            }
+
+            if self.eat(&Token::Close('}')) {
+                return Some(DebugNode::Map(entries));
+            }
+
+            let key = self.value()?;
+
+            if !self.eat(&Token::Colon) {
+                return None;
+            }
+
+            entries.push((key, self.value()?));
+        }
+    }
+}
+
+fn classify(atom: String) -> DebugNode {
+    if atom == "true" || atom == "false" {
+        return DebugNode::Bool(atom);
+    }
+
+    let digits = atom.strip_prefix('-').unwrap_or(&atom);
+
+    if digits.starts_with(|c: char| c.is_ascii_digit()) || digits == "inf" || digits == "NaN" {
+        return DebugNode::Number(atom);
+    }
+
+    DebugNode::Atom(atom)
+}
+
+fn render(node: &DebugNode, depth: usize, options: &DumpOptions, html: &mut String) {
+    if options.max_depth.is_some_and(|max| depth > max) {
+        if let Some(summary) = summarise(node) {
+            span(html, STYLE_MUTED, &summary);
+            return;
+        }
+    }
+
+    match node {
+        DebugNode::Null => span(html, STYLE_CONST, "null"),
+        DebugNode::Str(value) | DebugNode::Char(value) => span(html, STYLE_STRING, value),
+        DebugNode::Number(value) => span(html, STYLE_NUMBER, value),
+        DebugNode::Bool(value) => span(html, STYLE_CONST, value),
+        DebugNode::Atom(value) => span(html, STYLE_TYPE, value),
+        DebugNode::Struct {
+            name,
+            fields,
+            non_exhaustive,
+        } => {
+            if fields.is_empty() && !non_exhaustive {
+                span(html, STYLE_TYPE, name);
+                html.push_str(" {}");
+                return;
+            }
+
+            let summary = format!("{} fields", fields.len());
+
+            open(
+                html,
+                depth < options.expand_depth,
+                Some(name),
+                "{",
+                &summary,
+            );
+
+            for (key, value) in fields {
+                row_start(html);
+                span(html, STYLE_KEY, key);
+                html.push_str(": ");
+                render(value, depth + 1, options, html);
+                row_end(html);
+            }
+
+            if *non_exhaustive {
+                row_start(html);
+                span(html, STYLE_MUTED, "..");
+                row_end(html);
+            }
+
+            close(html, "}");
+        }
+        DebugNode::Tuple { name, items } => {
+            if items.is_empty() {
+                if let Some(name) = name {
+                    span(html, STYLE_TYPE, name);
+                }
+                html.push_str("()");
+                return;
+            }
+
+            let summary = format!("{} items", items.len());
+
+            open(
+                html,
+                depth < options.expand_depth,
+                name.as_deref(),
+                "(",
+                &summary,
+            );
+            list(html, depth, options, items);
+            close(html, ")");
+        }
+        DebugNode::Seq(items) => {
+            if items.is_empty() {
+                html.push_str("[]");
+                return;
+            }
+
+            let summary = format!("{} items", items.len());
+
+            open(html, depth < options.expand_depth, None, "[", &summary);
+
+            for (index, item) in items.iter().enumerate() {
+                row_start(html);
+                span(html, STYLE_MUTED, &index.to_string());
+                html.push_str(" =&gt; ");
+                render(item, depth + 1, options, html);
+                row_end(html);
+            }
+
+            close(html, "]");
+        }
+        DebugNode::Set(items) => {
+            let summary = format!("{} items", items.len());
+
+            open(html, depth < options.expand_depth, None, "{", &summary);
+            list(html, depth, options, items);
+            close(html, "}");
+        }
+        DebugNode::Map(entries) => {
+            if entries.is_empty() {
+                html.push_str("{}");
+                return;
+            }
+
+            let summary = format!("{} entries", entries.len());
+
+            open(html, depth < options.expand_depth, None, "{", &summary);
+
+            for (key, value) in entries {
+                row_start(html);
+                render(key, depth + 1, options, html);
+                html.push_str(" =&gt; ");
+                render(value, depth + 1, options, html);
+                row_end(html);
+            }
+
+            close(html, "}");
+        }
+    }
+}
+
+// Containers past the max depth are replaced by a one line summary, leaves are always shown
+fn summarise(node: &DebugNode) -> Option<String> {
+    let (name, open, close, count) = match node {
+        DebugNode::Struct { name, fields, .. } => (Some(name), "{", "}", fields.len()),
+        DebugNode::Tuple { name, items } => (name.as_ref(), "(", ")", items.len()),
+        DebugNode::Seq(items) => (None, "[", "]", items.len()),
+        DebugNode::Set(items) => (None, "{", "}", items.len()),
+        DebugNode::Map(entries) => (None, "{", "}", entries.len()),
+        _ => return None,
+    };
+
+    let name = name.map(|name| format!("{} ", name)).unwrap_or_default();
+
+    Some(format!("{}{}…{} ({})", name, open, close, count))
+}
+
+fn list(html: &mut String, depth: usize, options: &DumpOptions, items: &[DebugNode]) {
+    for item in items {
+        row_start(html);
+        render(item, depth + 1, options, html);
+        row_end(html);
+    }
+}
+
+pub(crate) fn span(html: &mut String, style: &str, value: &str) {
+    html.push_str(&format!(
+        "<span style=\"{}\">{}</span>",
+        style,
+        escape_html(value)
+    ));
+}
+
+fn open(html: &mut String, expanded: bool, name: Option<&str>, delimiter: &str, summary: &str) {
+    let open = if expanded { " open" } else { "" };
+
+    html.push_str(&format!(
+        "<details{} style=\"display: inline;\"><summary style=\"{}\">",
+        open, STYLE_SUMMARY
+    ));
+
+    if let Some(name) = name {
+        span(html, STYLE_TYPE, name);
+        html.push(' ');
+    }
+
+    html.push_str(delimiter);
+    html.push(' ');
+    span(html, STYLE_MUTED, summary);
+    html.push_str(&format!("</summary><div style=\"{}\">", STYLE_CHILDREN));
+}
+
+fn close(html: &mut String, delimiter: &str) {
+    html.push_str("</div>");
+    html.push_str(delimiter);
+    html.push_str("</details>");
+}
+
+fn row_start(html: &mut String) {
+    html.push_str("<div>");
+}
+
+fn row_end(html: &mut String) {
+    html.push_str("</div>");
+}
@@ -0,0 +1,261 @@
+// Helpers for Ray::file. Files are read a line at a time and only as far as needed, and only the
+// start of long lines is kept, so pointing it at a huge log or minified JSON doesn't read the
+// whole thing into memory.
+
+use crate::dumper::{escape_html, STYLE_MUTED, STYLE_ROOT};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+
+const STYLE_LINE: &str = "display: block;";
+const STYLE_HIGHLIGHT: &str = "display: block; background-color: #fef3c7;";
+const STYLE_NUMBER: &str = "color: #9ca3af; user-select: none;";
+
+// Bytes looked at to decide whether a file is text
+const SNIFF_BYTES: u64 = 8192;
+
+#[derive(Debug, Clone, Copy)]
+pub struct RayFileOptions {
+    // First and last line to show, starting at 1, None shows the whole file
+    pub lines: Option<(usize, usize)>,
+    // Line to draw attention to
+    pub highlight: Option<usize>,
+    // Stop after this many lines
+    pub max_lines: usize,
+    // Longer lines are cut off after this many bytes
+    pub max_line_bytes: usize,
+    // Stop once this many bytes of text have been shown
+    pub max_text_bytes: usize,
+    // Binary files show a hexdump of this many bytes from the start
+    pub max_bytes: usize,
+}
+
+impl Default for RayFileOptions {
+    fn default() -> Self {
+        Self {
+            lines: None,
+            highlight: None,
+            max_lines: 1000,
+            max_line_bytes: 1000,
+            max_text_bytes: 256 * 1024,
+            max_bytes: 512,
+        }
+    }
+}
+
+// Renders a text file with line numbers, or a hexdump if it isn't text
+pub fn file_html(path: &Path, options: &RayFileOptions) -> std::io::Result<String> {
+    let mut start = Vec::new();
+    File::open(path)?
+        .take(SNIFF_BYTES)
+        .read_to_end(&mut start)?;
+
+    if is_binary(&start) {
+        let mut bytes = Vec::new();
+        let file = File::open(path)?;
+        let size = file.metadata()?.len();
+
+        file.take(options.max_bytes as u64)
+            .read_to_end(&mut bytes)?;
+
+        return Ok(hexdump(&bytes, size));
+    }
+
+    let (first, last) = options.lines.unwrap_or((1, usize::MAX));
+    let first = first.max(1);
+
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut html = String::new();
+    let mut buffer = Vec::new();
+    let mut number = 0;
+    let mut shown = 0;
+    let mut shown_bytes = 0;
+
+    let width = last
+        .min(first.saturating_add(options.max_lines))
+        .to_string()
+        .len()
+        .max(3);
+
+    loop {
+        buffer.clear();
+
+        // Lines before the range are skipped without keeping any of them
+        let keep = if number + 1 < first {
+            0
+        } else {
+            options.max_line_bytes
+        };
+
+        let Some(length) = read_line(&mut reader, &mut buffer, keep)? else {
+            break;
+        };
+
+        number += 1;
+
+        if number < first {
+            continue;
+        }
+
+        if number > last {
+            break;
+        }
+
+        if shown == options.max_lines || shown_bytes >= options.max_text_bytes {
+            html.push_str(&format!(
+                "<span style=\"{}\">… truncated after {} lines</span>",
+                STYLE_MUTED, shown
+            ));
+            break;
+        }
+
+        // Don't leave half a character at the end of a line that was cut off
+        if buffer.len() < length {
+            if let Err(error) = std::str::from_utf8(&buffer) {
+                if error.error_len().is_none() {
+                    buffer.truncate(error.valid_up_to());
+                }
+            }
+        }
+
+        let cut = length - buffer.len();
+        let line = String::from_utf8_lossy(&buffer);
+        let style = if options.highlight == Some(number) {
+            STYLE_HIGHLIGHT
+        } else {
+            STYLE_LINE
+        };
+
+        let more = if cut > 0 {
+            format!(
+                "<span style=\"{}\"> … {} more bytes</span>",
+                STYLE_MUTED, cut
+            )
+        } else {
+            String::new()
+        };
+
+        html.push_str(&format!(
+            "<span style=\"{}\"><span style=\"{}\">{:>width$}</span>  {}{}</span>",
+            style,
+            STYLE_NUMBER,
+            number,
+            escape_html(&line),
+            more,
+            width = width
+        ));
+
+        shown += 1;
+        shown_bytes += buffer.len();
+    }
+
+    if shown == 0 {
+        html.push_str(&format!(
+            "<span style=\"{}\">No lines to show</span>",
+            STYLE_MUTED
+        ));
+    }
+
+    Ok(format!("<pre style=\"{}\">{}</pre>", STYLE_ROOT, html))
+}
+
+// Reads the next line, keeping at most `keep` bytes of it in the buffer and skipping the rest.
+// Gives the length of the whole line without its line ending, or None at the end of the file
+fn read_line<R: BufRead>(
+    reader: &mut R,
+    buffer: &mut Vec<u8>,
+    keep: usize,
+) -> std::io::Result<Option<usize>> {
+    let mut length = 0;
+    let mut read_any = false;
+    let mut last = None;
+
+    loop {
+        let available = reader.fill_buf()?;
+
+        if available.is_empty() {
+            break;
+        }
+
+        read_any = true;
+
+        let (chunk, consumed, done) = match available.iter().position(|&byte| byte == b'\n') {
+            Some(end) => (&available[..end], end + 1, true),
+            None => (available, available.len(), false),
+        };
+
+        let room = keep.saturating_sub(buffer.len()).min(chunk.len());
+        buffer.extend_from_slice(&chunk[..room]);
+        length += chunk.len();
+        last = chunk.last().copied().or(last);
+
+        reader.consume(consumed);
+
+        if done {
+            break;
+        }
+    }
+
+    if !read_any {
+        return Ok(None);
+    }
+
+    // Windows line endings
+    if last == Some(b'\r') {
+        length -= 1;
+        buffer.truncate(length);
+    }
+
+    Ok(Some(length))
+}
+
+// Text files don't contain NUL bytes and are valid UTF-8, allowing for a character that was cut
+// in half at the end of the sample
+fn is_binary(bytes: &[u8]) -> bool {
+    if bytes.contains(&0) {
+        return true;
+    }
+
+    match std::str::from_utf8(bytes) {
+        Ok(_) => false,
+        Err(error) => error.error_len().is_some(),
+    }
+}
+
+// Offsets, hex and the printable ASCII of each 16 byte row, like `hexdump -C`
+pub fn hexdump(bytes: &[u8], size: u64) -> String {
+    let mut html = String::new();
+
+    for (row, chunk) in bytes.chunks(16).enumerate() {
+        let hex: Vec<String> = chunk.iter().map(|byte| format!("{:02x}", byte)).collect();
+        let ascii: String = chunk
+            .iter()
+            .map(|&byte| {
+                if byte.is_ascii_graphic() || byte == b' ' {
+                    byte as char
+                } else {
+                    '.'
+                }
+            })
+            .collect();
+
+        html.push_str(&format!(
+            "<span style=\"{}\"><span style=\"{}\">{:08x}</span>  {:<47}  |{}|</span>",
+            STYLE_LINE,
+            STYLE_NUMBER,
+            row * 16,
+            hex.join(" "),
+            escape_html(&ascii)
+        ));
+    }
+
+    if size > bytes.len() as u64 {
+        html.push_str(&format!(
+            "<span style=\"{}\">… {} more bytes</span>",
+            STYLE_MUTED,
+            size - bytes.len() as u64
+        ));
+    }
+
+    format!("<pre style=\"{}\">{}</pre>", STYLE_ROOT, html)
+}
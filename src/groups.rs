@@ -0,0 +1,46 @@
+// Ray::group colours everything sent from inside its closure. The colour lives in a thread local
+// stack rather than on a Ray instance, so the ray! calls deep inside a request handler pick it up
+// without being handed anything.
+
+use crate::message::RayColor;
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+const COLORS: [RayColor; 5] = [
+    RayColor::Blue,
+    RayColor::Green,
+    RayColor::Orange,
+    RayColor::Purple,
+    RayColor::Red,
+];
+
+static NEXT_COLOR: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    static GROUPS: RefCell<Vec<RayColor>> = const { RefCell::new(Vec::new()) };
+}
+
+// Groups that follow each other get different colours so they're easy to tell apart
+pub fn next_color() -> RayColor {
+    COLORS[NEXT_COLOR.fetch_add(1, Ordering::Relaxed) % COLORS.len()]
+}
+
+// The colour of the innermost group on this thread, if there is one
+pub fn current() -> Option<RayColor> {
+    GROUPS.with(|groups| groups.borrow().last().copied())
+}
+
+// Leaves the group when dropped, even if the closure panicked
+pub struct RayGroupGuard;
+
+pub fn enter(color: RayColor) -> RayGroupGuard {
+    GROUPS.with(|groups| groups.borrow_mut().push(color));
+
+    RayGroupGuard
+}
+
+impl Drop for RayGroupGuard {
+    fn drop(&mut self) {
+        GROUPS.with(|groups| groups.borrow_mut().pop());
+    }
+}
@@ -0,0 +1,400 @@
+// Helpers for Ray::image. Images are embedded in the payload as data URLs rather than pointing Ray
+// at a file, so an image that gets overwritten later (like the frames of a pipeline written to the
+// same path) still shows what it looked like when it was sent.
+
+use crate::dumper::{escape_html, STYLE_CAPTION};
+use std::path::{Path, PathBuf};
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+// Where an image comes from. Strings are guessed: URLs and data URLs are used as they are,
+// existing files are read and anything else that decodes as base64 is treated as image data
+#[derive(Debug, Clone, PartialEq)]
+pub enum RayImage {
+    Path(PathBuf),
+    Bytes(Vec<u8>),
+    Base64(String),
+    Url(String),
+}
+
+impl From<&str> for RayImage {
+    fn from(value: &str) -> Self {
+        let value = value.trim();
+
+        if value.starts_with("http://") || value.starts_with("https://") {
+            return RayImage::Url(value.to_string());
+        }
+
+        if value.starts_with("data:") || (!Path::new(value).exists() && is_base64(value)) {
+            return RayImage::Base64(value.to_string());
+        }
+
+        RayImage::Path(PathBuf::from(value))
+    }
+}
+
+impl From<String> for RayImage {
+    fn from(value: String) -> Self {
+        RayImage::from(value.as_str())
+    }
+}
+
+impl From<&Path> for RayImage {
+    fn from(value: &Path) -> Self {
+        RayImage::Path(value.to_path_buf())
+    }
+}
+
+impl From<PathBuf> for RayImage {
+    fn from(value: PathBuf) -> Self {
+        RayImage::Path(value)
+    }
+}
+
+impl From<&[u8]> for RayImage {
+    fn from(value: &[u8]) -> Self {
+        RayImage::Bytes(value.to_vec())
+    }
+}
+
+impl From<Vec<u8>> for RayImage {
+    fn from(value: Vec<u8>) -> Self {
+        RayImage::Bytes(value)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct RayImageOptions {
+    // PNGs bigger than this are scaled down until they fit, other images aren't sent. None sends
+    // everything
+    pub max_bytes: Option<usize>,
+    // Bigger PNGs are scaled down to fit before they're sent, keeping their aspect ratio. Other
+    // formats can't be decoded here, so they're only shown at that size
+    pub max_width: Option<u32>,
+    pub max_height: Option<u32>,
+}
+
+impl Default for RayImageOptions {
+    fn default() -> Self {
+        Self {
+            max_bytes: Some(5 * 1024 * 1024),
+            max_width: None,
+            max_height: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ImageInfo {
+    pub mime: &'static str,
+    pub width: u32,
+    pub height: u32,
+}
+
+// Builds the `<img>` Ray shows, or a message saying why the image can't be shown
+pub fn image_html(image: &RayImage, options: &RayImageOptions) -> Result<String, String> {
+    let bytes = match image {
+        RayImage::Url(url) => return Ok(img(url, None, options)),
+        RayImage::Path(path) => std::fs::read(path)
+            .map_err(|error| format!("Couldn't read image {}: {}", path.display(), error))?,
+        RayImage::Bytes(bytes) => bytes.clone(),
+        RayImage::Base64(value) => {
+            // Only the data is needed, the MIME type of a data URL is sniffed again below
+            let data = match value.strip_prefix("data:") {
+                Some(url) => url.split_once(',').map(|(_, data)| data).unwrap_or(""),
+                None => value,
+            };
+
+            decode_base64(data).ok_or_else(|| "Image isn't valid base64".to_string())?
+        }
+    };
+
+    let original = sniff(&bytes);
+
+    // PNGs can be scaled down to fit the limits, anything else has to fit as it is
+    let (bytes, info) = match original {
+        Some(info) if info.mime == "image/png" => {
+            let (bytes, info) = fit_png(bytes, info, options);
+
+            (bytes, Some(info))
+        }
+        _ => (bytes, original),
+    };
+
+    if let Some(max_bytes) = options.max_bytes {
+        if bytes.len() > max_bytes {
+            return Err(format!(
+                "Image is {}, which is over the {} limit",
+                format_bytes(bytes.len()),
+                format_bytes(max_bytes)
+            ));
+        }
+    }
+
+    let info = info.ok_or_else(|| "Image isn't a PNG, JPEG, GIF or WebP".to_string())?;
+    let src = format!("data:{};base64,{}", info.mime, encode_base64(&bytes));
+
+    let mut caption = format!("{} {}×{}", info.mime, info.width, info.height);
+
+    if let Some(original) = original.filter(|original| *original != info) {
+        caption.push_str(&format!(
+            " (scaled down from {}×{})",
+            original.width, original.height
+        ));
+    }
+
+    caption.push_str(&format!(", {}", format_bytes(bytes.len())));
+
+    Ok(img(&src, Some(&caption), options))
+}
+
+// Scales a PNG down to fit max_width and max_height, then further until it's under max_bytes. A
+// PNG that can't be decoded is left as it is
+fn fit_png(bytes: Vec<u8>, info: ImageInfo, options: &RayImageOptions) -> (Vec<u8>, ImageInfo) {
+    let over_bytes = |length: usize| {
+        options
+            .max_bytes
+            .is_some_and(|max_bytes| length > max_bytes)
+    };
+
+    let scale = [
+        options.max_width.map(|max| max as f64 / info.width as f64),
+        options
+            .max_height
+            .map(|max| max as f64 / info.height as f64),
+    ]
+    .into_iter()
+    .flatten()
+    .fold(1.0f64, f64::min);
+
+    if scale >= 1.0 && !over_bytes(bytes.len()) {
+        return (bytes, info);
+    }
+
+    let Some((rgba, width, height)) = crate::png::decode(&bytes) else {
+        return (bytes, info);
+    };
+
+    let mut scale = scale.min(1.0);
+
+    loop {
+        let scaled_width = ((width as f64 * scale).round() as u32).max(1);
+        let scaled_height = ((height as f64 * scale).round() as u32).max(1);
+
+        let png = crate::png::encode(
+            &downscale(&rgba, width, height, scaled_width, scaled_height),
+            scaled_width,
+            scaled_height,
+            6,
+            4,
+        );
+
+        if !over_bytes(png.len()) || (scaled_width == 1 && scaled_height == 1) {
+            let info = ImageInfo {
+                mime: "image/png",
+                width: scaled_width,
+                height: scaled_height,
+            };
+
+            return (png, info);
+        }
+
+        scale *= 0.75;
+    }
+}
+
+// Each pixel is the average of the block of pixels it covers
+fn downscale(
+    rgba: &[u8],
+    width: u32,
+    height: u32,
+    scaled_width: u32,
+    scaled_height: u32,
+) -> Vec<u8> {
+    let (width, height) = (width as usize, height as usize);
+    let (scaled_width, scaled_height) = (scaled_width as usize, scaled_height as usize);
+    let mut scaled = Vec::with_capacity(scaled_width * scaled_height * 4);
+
+    for y in 0..scaled_height {
+        let top = y * height / scaled_height;
+        let bottom = ((y + 1) * height / scaled_height).max(top + 1);
+
+        for x in 0..scaled_width {
+            let left = x * width / scaled_width;
+            let right = ((x + 1) * width / scaled_width).max(left + 1);
+            let mut sum = [0u64; 4];
+
+            for row in top..bottom {
+                for pixel in rgba[(row * width + left) * 4..(row * width + right) * 4].chunks(4) {
+                    for (total, &value) in sum.iter_mut().zip(pixel) {
+                        *total += value as u64;
+                    }
+                }
+            }
+
+            let count = ((bottom - top) * (right - left)) as u64;
+            scaled.extend(sum.iter().map(|total| ((total + count / 2) / count) as u8));
+        }
+    }
+
+    scaled
+}
+
+fn img(src: &str, caption: Option<&str>, options: &RayImageOptions) -> String {
+    let mut style = String::new();
+
+    if let Some(width) = options.max_width {
+        style.push_str(&format!("max-width: {}px; ", width));
+    }
+
+    if let Some(height) = options.max_height {
+        style.push_str(&format!("max-height: {}px; ", height));
+    }
+
+    let mut html = format!(
+        "<img src=\"{}\" alt=\"\" style=\"{}\" />",
+        escape_html(src),
+        style.trim_end()
+    );
+
+    if let Some(caption) = caption {
+        html.push_str(&format!(
+            "<div style=\"{}\">{}</div>",
+            STYLE_CAPTION,
+            escape_html(caption)
+        ));
+    }
+
+    html
+}
+
+// Works out the format and dimensions of an image from its header
+pub fn sniff(bytes: &[u8]) -> Option<ImageInfo> {
+    let u16_be = |at: usize| Some(u16::from_be_bytes(bytes.get(at..at + 2)?.try_into().ok()?));
+    let u16_le = |at: usize| Some(u16::from_le_bytes(bytes.get(at..at + 2)?.try_into().ok()?));
+    let u32_be = |at: usize| Some(u32::from_be_bytes(bytes.get(at..at + 4)?.try_into().ok()?));
+    let u24_le = |at: usize| {
+        let b = bytes.get(at..at + 3)?;
+        Some(b[0] as u32 | (b[1] as u32) << 8 | (b[2] as u32) << 16)
+    };
+
+    let info = |mime, width, height| {
+        Some(ImageInfo {
+            mime,
+            width,
+            height,
+        })
+    };
+
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        return info("image/png", u32_be(16)?, u32_be(20)?);
+    }
+
+    if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+        return info("image/gif", u16_le(6)? as u32, u16_le(8)? as u32);
+    }
+
+    if bytes.starts_with(b"RIFF") && bytes.get(8..12) == Some(b"WEBP") {
+        return match bytes.get(12..16)? {
+            b"VP8 " => info(
+                "image/webp",
+                (u16_le(26)? & 0x3fff) as u32,
+                (u16_le(28)? & 0x3fff) as u32,
+            ),
+            b"VP8L" => {
+                let b = bytes.get(21..25)?;
+                let width = 1 + (b[0] as u32 | (b[1] as u32 & 0x3f) << 8);
+                let height =
+                    1 + (b[1] as u32 >> 6 | (b[2] as u32) << 2 | (b[3] as u32 & 0xf) << 10);
+
+                info("image/webp", width, height)
+            }
+            b"VP8X" => info("image/webp", 1 + u24_le(24)?, 1 + u24_le(27)?),
+            _ => None,
+        };
+    }
+
+    if bytes.starts_with(b"\xff\xd8\xff") {
+        // Walk the segments until the start of frame, which holds the dimensions
+        let mut at = 2;
+
+        while *bytes.get(at)? == 0xff {
+            let marker = *bytes.get(at + 1)?;
+
+            if (0xc0..=0xcf).contains(&marker) && ![0xc4, 0xc8, 0xcc].contains(&marker) {
+                return info("image/jpeg", u16_be(at + 7)? as u32, u16_be(at + 5)? as u32);
+            }
+
+            at += 2 + u16_be(at + 2)? as usize;
+        }
+    }
+
+    None
+}
+
+pub fn encode_base64(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+
+    for chunk in bytes.chunks(3) {
+        let n = (chunk[0] as u32) << 16
+            | (*chunk.get(1).unwrap_or(&0) as u32) << 8
+            | *chunk.get(2).unwrap_or(&0) as u32;
+
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(BASE64[(n >> (18 - i * 6)) as usize & 0x3f] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+
+    encoded
+}
+
+pub fn decode_base64(value: &str) -> Option<Vec<u8>> {
+    let value: Vec<u8> = value.bytes().filter(|b| !b.is_ascii_whitespace()).collect();
+
+    if value.is_empty() || value.len() % 4 != 0 {
+        return None;
+    }
+
+    let mut decoded = Vec::with_capacity(value.len() / 4 * 3);
+
+    let chunks = value.len() / 4;
+
+    for (index, chunk) in value.chunks(4).enumerate() {
+        let padding = chunk.iter().rev().take_while(|&&b| b == b'=').count();
+
+        // Padding is only allowed at the very end
+        if padding > 0 && index + 1 < chunks {
+            return None;
+        }
+        let mut n = 0u32;
+
+        for (i, &b) in chunk.iter().enumerate() {
+            let digit = match b {
+                b'=' if i >= 4 - padding && padding <= 2 => 0,
+                _ => BASE64.iter().position(|&c| c == b)? as u32,
+            };
+
+            n = n << 6 | digit;
+        }
+
+        decoded.extend_from_slice(&n.to_be_bytes()[1..4 - padding]);
+    }
+
+    Some(decoded)
+}
+
+fn is_base64(value: &str) -> bool {
+    value.len() >= 8 && decode_base64(value).is_some()
+}
+
+fn format_bytes(bytes: usize) -> String {
+    match bytes {
+        0..=1023 => format!("{} B", bytes),
+        1024..=1048575 => format!("{:.1} KB", bytes as f64 / 1024.0),
+        _ => format!("{:.1} MB", bytes as f64 / 1048576.0),
+    }
+}
@@ -0,0 +1,158 @@
+// Ray::info, the closest thing to phpinfo. A library can't see its caller's Cargo metadata at
+// compile time, but cargo run and cargo test export CARGO_PKG_* to the process, so those are read
+// at runtime along with everything else.
+
+use crate::dumper::escape_html;
+use crate::message::RayTableValues;
+use crate::RayMeta;
+
+// Shown by info() when they're set
+pub const DEFAULT_ENV: [&str; 9] = [
+    "RUST_BACKTRACE",
+    "RUST_LOG",
+    "RUST_MIN_STACK",
+    "RUSTFLAGS",
+    "CARGO_MANIFEST_DIR",
+    "CARGO_TARGET_DIR",
+    "TOKIO_WORKER_THREADS",
+    "RAY_REMOTE_PATH",
+    "RAY_LOCAL_PATH",
+];
+
+pub fn info_values(meta: &RayMeta, env: &[&str]) -> RayTableValues {
+    let mut rows: Vec<(String, String)> = vec![
+        ("rustc".to_string(), meta.rustc_version.clone()),
+        ("ray-rust".to_string(), meta.package_version.clone()),
+        ("target".to_string(), target()),
+        ("profile".to_string(), profile().to_string()),
+        ("features".to_string(), features()),
+    ];
+
+    let mut package: Vec<(String, String)> = std::env::vars()
+        .filter(|(key, _)| key.starts_with("CARGO_PKG_"))
+        .collect();
+    package.sort();
+    rows.extend(package);
+
+    rows.push(("os".to_string(), os()));
+    rows.push((
+        "cpus".to_string(),
+        std::thread::available_parallelism()
+            .map(|cpus| cpus.to_string())
+            .unwrap_or_else(|_| unknown()),
+    ));
+    rows.push((
+        "threads".to_string(),
+        threads()
+            .map(|threads| threads.to_string())
+            .unwrap_or_else(unknown),
+    ));
+    rows.push(("pid".to_string(), std::process::id().to_string()));
+    rows.push((
+        "working directory".to_string(),
+        std::env::current_dir()
+            .map(|dir| dir.display().to_string())
+            .unwrap_or_else(|_| unknown()),
+    ));
+    rows.push((
+        "executable".to_string(),
+        std::env::current_exe()
+            .map(|exe| exe.display().to_string())
+            .unwrap_or_else(|_| unknown()),
+    ));
+
+    for key in env {
+        if rows.iter().any(|(shown, _)| shown == key) {
+            continue;
+        }
+
+        if let Ok(value) = std::env::var(key) {
+            rows.push((key.to_string(), value));
+        }
+    }
+
+    RayTableValues(
+        rows.into_iter()
+            .map(|(key, value)| (key, escape_html(&value)))
+            .collect(),
+    )
+}
+
+fn unknown() -> String {
+    "unknown".to_string()
+}
+
+fn target() -> String {
+    let env = if cfg!(target_env = "gnu") {
+        "-gnu"
+    } else if cfg!(target_env = "musl") {
+        "-musl"
+    } else if cfg!(target_env = "msvc") {
+        "-msvc"
+    } else {
+        ""
+    };
+
+    format!(
+        "{}-{}{} ({})",
+        std::env::consts::ARCH,
+        std::env::consts::OS,
+        env,
+        std::env::consts::FAMILY
+    )
+}
+
+fn profile() -> &'static str {
+    if cfg!(debug_assertions) {
+        "debug"
+    } else {
+        "release"
+    }
+}
+
+// Features of this crate, the caller's aren't visible to us
+fn features() -> String {
+    let features: Vec<&str> = [
+        ("with_tokio", cfg!(feature = "with_tokio")),
+        ("anyhow", cfg!(feature = "anyhow")),
+        ("eyre", cfg!(feature = "eyre")),
+    ]
+    .into_iter()
+    .filter(|(_, enabled)| *enabled)
+    .map(|(feature, _)| feature)
+    .collect();
+
+    if features.is_empty() {
+        "none".to_string()
+    } else {
+        features.join(", ")
+    }
+}
+
+// The distribution and kernel on Linux, just the OS name everywhere else
+fn os() -> String {
+    let name = std::fs::read_to_string("/etc/os-release")
+        .ok()
+        .and_then(|release| {
+            release.lines().find_map(|line| {
+                line.strip_prefix("PRETTY_NAME=")
+                    .map(|name| name.trim_matches('"').to_string())
+            })
+        })
+        .unwrap_or_else(|| std::env::consts::OS.to_string());
+
+    match std::fs::read_to_string("/proc/sys/kernel/osrelease") {
+        Ok(kernel) => format!("{}, kernel {}", name, kernel.trim()),
+        Err(_) => name,
+    }
+}
+
+fn threads() -> Option<usize> {
+    std::fs::read_to_string("/proc/self/status")
+        .ok()?
+        .lines()
+        .find_map(|line| line.strip_prefix("Threads:"))?
+        .trim()
+        .parse()
+        .ok()
+}
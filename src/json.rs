@@ -0,0 +1,85 @@
+// Helpers for Ray::json. The input is re-indented as text rather than parsed into a
+// serde_json::Value, since that would sort the keys of every object.
+
+use crate::dumper::error_snippet_html;
+
+const INDENT: &str = "    ";
+
+// Checks the JSON is valid and pretty prints it, keeping keys in their original order
+pub fn pretty(json: &str) -> Result<String, serde_json::Error> {
+    serde_json::from_str::<serde::de::IgnoredAny>(json)?;
+
+    let mut pretty = String::with_capacity(json.len() * 2);
+    let mut depth = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut chars = json.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if in_string {
+            pretty.push(c);
+
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+
+            continue;
+        }
+
+        match c {
+            '"' => {
+                in_string = true;
+                pretty.push(c);
+            }
+            '{' | '[' => {
+                pretty.push(c);
+
+                // Keep empty objects and arrays on one line
+                while chars.peek().is_some_and(|c| c.is_whitespace()) {
+                    chars.next();
+                }
+
+                if matches!(chars.peek(), Some('}') | Some(']')) {
+                    pretty.push(chars.next().unwrap_or_default());
+                    continue;
+                }
+
+                depth += 1;
+                newline(&mut pretty, depth);
+            }
+            '}' | ']' => {
+                depth -= 1;
+                newline(&mut pretty, depth);
+                pretty.push(c);
+            }
+            ',' => {
+                pretty.push(c);
+                newline(&mut pretty, depth);
+            }
+            ':' => pretty.push_str(": "),
+            c if c.is_whitespace() => {}
+            c => pretty.push(c),
+        }
+    }
+
+    Ok(pretty)
+}
+
+// Shows where parsing failed: the message, and the offending line with a caret under the column
+pub fn error_html(json: &str, error: &serde_json::Error) -> String {
+    error_snippet_html(
+        "Invalid JSON",
+        json,
+        error.line(),
+        error.column(),
+        &error.to_string(),
+    )
+}
+
+fn newline(pretty: &mut String, depth: usize) {
+    pretty.push('\n');
+    pretty.push_str(&INDENT.repeat(depth));
+}
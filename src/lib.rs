@@ -1,12 +1,47 @@
 use rustc_version::version_meta;
 use serde::{Deserialize, Serialize};
-
+use std::sync::atomic::{AtomicBool, Ordering};
+
+mod counters;
+mod dumper;
+mod file;
+mod groups;
+mod image;
+mod info;
+mod json;
+mod link;
 mod message;
+mod panic;
+mod paths;
+#[cfg(feature = "with_tokio")]
+mod pending;
+mod pixels;
+mod png;
+#[cfg(any(feature = "anyhow", feature = "eyre"))]
+mod report;
+mod serde_dumper;
+mod stopwatches;
+mod table;
+mod trace;
+mod xml;
+pub use dumper::{debug_to_html, DumpOptions};
+pub use file::RayFileOptions;
+pub use image::{RayImage, RayImageOptions};
 use message::*;
+pub use message::{RayColor, RaySize};
+pub use panic::install_panic_hook;
+pub use pixels::{RayPixelData, RayPixelFormat, RayPixels};
+#[cfg(any(feature = "anyhow", feature = "eyre"))]
+pub use report::RayResultExt;
+pub use serde_dumper::serde_to_html;
 
 #[cfg(test)]
 mod tests;
 
+const RAY_URL: &str = "http://localhost:23517";
+
+static PANIC_ON_DIE: AtomicBool = AtomicBool::new(false);
+
 #[macro_export]
 macro_rules! ray {
     // If no arguments are passed, just create a new Ray instance
@@ -21,7 +56,7 @@ macro_rules! ray {
         let mut vec = Vec::new();
 
         // Push each argument to the vector
-        $(vec.push(format!("{:#?}", $arg));)*
+        $(vec.push($crate::debug_to_html(&format!("{:#?}", $arg)));)*
 
         ray.log(vec);
 
@@ -29,6 +64,20 @@ macro_rules! ray {
     }};
 }
 
+// Like Ray::pass, labelled with the expression that produced the value
+#[macro_export]
+macro_rules! ray_pass {
+    ($value:expr) => {{
+        let value = $value;
+
+        $crate::Ray::new()
+            .log(vec![$crate::debug_to_html(&format!("{:#?}", value))])
+            .label(stringify!($value));
+
+        value
+    }};
+}
+
 #[macro_export]
 macro_rules! rd {
     () => {{
@@ -42,7 +91,7 @@ macro_rules! rd {
         let mut ray = Ray::new();
         let mut vec = Vec::new();
 
-        $(vec.push(format!("{:#?}", $arg));)*
+        $(vec.push($crate::debug_to_html(&format!("{:#?}", $arg)));)*
 
         ray.log(vec);
 
@@ -73,25 +122,40 @@ pub struct RayOrigin {
     file: String,
     line_number: u32,
     hostname: String,
+    // Capturing a backtrace is slow, so the caller is only looked up once the payload is actually
+    // sent. A disabled instance never pays for it
+    #[serde(skip)]
+    resolved: bool,
+}
+
+impl Default for RayOrigin {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl RayOrigin {
     pub fn new() -> Self {
-        // TODO: Get the function name, file, and line number from the macro
-        // I don't think we can get the function name, file, and line number from the macro, at
-        // least not easily. So we'll just set them to empty strings and 0 for now.
-        let function_name = "ray".to_string();
-        let file = "".to_string();
-        let line_number = 0;
-        let hostname = "localhost".to_string();
-
         Self {
-            function_name,
-            file,
-            line_number,
-            hostname,
+            function_name: "ray".to_string(),
+            file: "".to_string(),
+            line_number: 0,
+            hostname: "localhost".to_string(),
+            resolved: false,
         }
     }
+
+    // The first frame outside of ray-rust is the code that called us. Without debug info there
+    // may not be one, so the placeholder values are kept
+    fn resolve(&mut self, frame: Option<&trace::RayFrame>) {
+        if let Some(frame) = frame {
+            self.function_name = frame.function.clone();
+            self.file = paths::map(&frame.file);
+            self.line_number = frame.line_number;
+        }
+
+        self.resolved = true;
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -102,6 +166,12 @@ pub struct RayMeta {
     package_version: String,
 }
 
+impl Default for RayMeta {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl RayMeta {
     pub fn new() -> Self {
         let rustc_version = match version_meta() {
@@ -116,11 +186,62 @@ impl RayMeta {
     }
 }
 
+#[derive(Debug, Clone, Copy)]
+pub struct RayPauseOptions {
+    // How often to ask Ray whether the lock has been released
+    pub poll_interval: std::time::Duration,
+    // Give up waiting and carry on after this long, None waits until continue is clicked
+    pub timeout: Option<std::time::Duration>,
+    // Whether clicking stop execution in Ray should end the process
+    pub stop_execution: bool,
+}
+
+impl Default for RayPauseOptions {
+    fn default() -> Self {
+        Self {
+            poll_interval: std::time::Duration::from_secs(1),
+            timeout: None,
+            stop_execution: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RayTraceOptions {
+    // Only send this many frames, None sends all of them
+    pub limit: Option<usize>,
+    // Skip this many frames, 0 starts at the function that called trace
+    pub offset: usize,
+}
+
+// Lets show_when and remove_when take either a bool or a closure that's only run when needed
+pub trait RayCondition {
+    fn evaluate(self) -> bool;
+}
+
+impl RayCondition for bool {
+    fn evaluate(self) -> bool {
+        self
+    }
+}
+
+impl<F: FnOnce() -> bool> RayCondition for F {
+    fn evaluate(self) -> bool {
+        self()
+    }
+}
+
 pub struct Ray {
     request: RayPayload,
     is_enabled: bool,
 }
 
+impl Default for Ray {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Ray {
     pub fn new() -> Self {
         Self {
@@ -136,37 +257,138 @@ impl Ray {
     #[cfg(feature = "with_tokio")]
     pub fn send(&mut self) {
         if !self.is_enabled {
-            return ();
+            return;
         }
 
-        let request = self.request.clone();
+        let request = self.outgoing();
+        let pending = pending::start();
 
-        let _ = tokio::task::spawn_blocking(move || {
+        // Nothing waits on the handle, die counts on the pending guard instead
+        drop(tokio::task::spawn_blocking(move || {
             let client = reqwest::blocking::Client::new();
 
-            let _ = client.post("http://localhost:23517").json(&request).send();
-        });
+            let _ = client.post(RAY_URL).json(&request).send();
+
+            drop(pending);
+        }));
     }
 
     #[cfg(not(feature = "with_tokio"))]
     pub fn send(&mut self) {
         if !self.is_enabled {
-            return ();
+            return;
         }
 
-        let request = self.request.clone();
+        let request = self.outgoing();
 
         let client = reqwest::blocking::Client::new();
 
-        let _ = client.post("http://localhost:23517").json(&request).send();
+        let _ = client.post(RAY_URL).json(&request).send();
+    }
+
+    // For payloads that do something in the app rather than show up in it. They go out in a
+    // request of their own, otherwise every later call on this instance would post them again and
+    // the app would pop up a notification or show itself each time
+    fn send_alone(&mut self, content: RayContent) -> &mut Self {
+        let mut ray = Ray::new();
+
+        ray.is_enabled = self.is_enabled;
+        ray.request.payloads.push(content);
+        ray.send();
+
+        self
+    }
+
+    // Inside a group everything is sent in the group's colour, unless it picked its own
+    fn outgoing(&mut self) -> RayPayload {
+        // Everything waiting for an origin was added by the call that's sending it, so they share
+        // one backtrace
+        if self
+            .request
+            .payloads
+            .iter()
+            .any(|payload| !payload.origin.resolved)
+        {
+            let frame = trace::capture().into_iter().next();
+
+            for payload in &mut self.request.payloads {
+                if !payload.origin.resolved {
+                    payload.origin.resolve(frame.as_ref());
+                }
+            }
+        }
+
+        let mut request = self.request.clone();
+
+        let has_color = request
+            .payloads
+            .iter()
+            .any(|payload| matches!(payload.content, RayMessage::Color(_)));
+
+        if let (Some(color), false) = (groups::current(), has_color) {
+            let origin = match request.payloads.last() {
+                Some(payload) => payload.origin.clone(),
+                None => RayOrigin::new(),
+            };
+
+            request.payloads.push(RayContent {
+                content_type: RayColorPayload::get_type(),
+                origin,
+                content: RayMessage::Color(RayColorPayload { color }),
+            });
+        }
+
+        request
+    }
+
+    // Posts the request and waits for it to land, even with tokio where send only spawns it.
+    // reqwest's blocking client can't run on a runtime thread, so it goes out on a plain thread
+    fn flush(&mut self) {
+        if !self.is_enabled {
+            return;
+        }
+
+        let request = self.outgoing();
+
+        let _ = std::thread::spawn(move || {
+            let client = reqwest::blocking::Client::new();
+
+            let _ = client.post(RAY_URL).json(&request).send();
+        })
+        .join();
+    }
+
+    // Paths under remote are shown and linked as the same path under local, for code running in
+    // a container or on another machine
+    pub fn path_mapping(remote: &str, local: &str) {
+        paths::set(remote, local);
+    }
+
+    pub fn clear_path_mapping() {
+        paths::clear();
+    }
+
+    // Tests can't survive process::exit, so they can ask die to panic instead
+    pub fn panic_on_die(enabled: bool) {
+        PANIC_ON_DIE.store(enabled, Ordering::SeqCst);
     }
 
+    // Panicking only stops the current thread, exiting stops the whole program. Make sure
+    // everything has reached Ray first since exit doesn't wait for anything, including what other
+    // instances are still sending in the background with tokio
     pub fn die(&mut self, status: i32) {
-        panic!("exited with code {}", status);
+        if !self.request.payloads.is_empty() {
+            self.flush();
+        }
 
-        // TODO: I think we need to use process::exit here to actually exit the process since this
-        // doesn't stop threaded work but I can't see a nice way to test this. I'm going to leave it
-        // std::process::exit(status);
+        #[cfg(feature = "with_tokio")]
+        pending::wait();
+
+        if PANIC_ON_DIE.load(Ordering::SeqCst) {
+            panic!("exited with code {}", status);
+        }
+
+        std::process::exit(status);
     }
 
     pub fn clear_all(&mut self) -> &mut Self {
@@ -226,6 +448,18 @@ impl Ray {
         self
     }
 
+    pub fn dump_serde<T: Serialize + ?Sized>(&mut self, value: &T) -> &mut Self {
+        self.dump_serde_with(value, DumpOptions::default())
+    }
+
+    pub fn dump_serde_with<T: Serialize + ?Sized>(
+        &mut self,
+        value: &T,
+        options: DumpOptions,
+    ) -> &mut Self {
+        self.log(vec![serde_to_html(value, options)])
+    }
+
     pub fn text(&mut self, value: &str) -> &mut Self {
         let message = RayMessage::Text(RayText {
             label: RayMessageType::Text,
@@ -244,13 +478,28 @@ impl Ray {
         self
     }
 
-    pub fn color(&mut self, value: &str) -> &mut Self {
-        let message = RayMessage::Color(RayColor {
-            color: RayColors::from(value.to_string()),
-        });
+    // The color goes out with the same uuid as everything else on this instance, so Ray applies
+    // it to the entry it's chained on rather than adding a new one
+    pub fn color(&mut self, color: RayColor) -> &mut Self {
+        let message = RayMessage::Color(RayColorPayload { color });
 
         let content = RayContent {
-            content_type: RayColor::get_type(),
+            content_type: RayColorPayload::get_type(),
+            origin: RayOrigin::new(),
+            content: message,
+        };
+
+        self.request.payloads.push(content);
+
+        self.send();
+        self
+    }
+
+    pub fn screen_color(&mut self, color: RayColor) -> &mut Self {
+        let message = RayMessage::ScreenColor(RayScreenColor { color });
+
+        let content = RayContent {
+            content_type: RayScreenColor::get_type(),
             origin: RayOrigin::new(),
             content: message,
         };
@@ -262,8 +511,8 @@ impl Ray {
     }
 
     pub fn html(&mut self, value: &str) -> &mut Self {
-        let message = RayMessage::HTML(RayHtml {
-            label: RayMessageType::HTML,
+        let message = RayMessage::Html(RayHtml {
+            label: RayMessageType::Html,
             content: value.to_string(),
         });
 
@@ -313,20 +562,98 @@ impl Ray {
         self
     }
 
-    pub fn count(&mut self, _name: &str) -> &mut Self {
-        // create a new counter hashmap with the name?
-        // increment the counter hashmap with the name?
-        // return a custom message with "called name x times"
+    // On stable an arbitrary std error can't hand us the backtrace it captured, so the frames
+    // are taken from where error was called instead
+    pub fn error<E: std::error::Error + RayErrorClass + ?Sized>(&mut self, error: &E) -> &mut Self {
+        let message = error_chain(error);
 
-        unimplemented!();
+        self.exception(&error.error_class(), &message, trace::capture())
     }
 
-    pub fn counter_value(&mut self) -> &mut Self {
-        unimplemented!();
+    #[cfg(feature = "anyhow")]
+    pub fn anyhow(&mut self, error: &anyhow::Error) -> &mut Self {
+        let message = error_chain(&**error);
+
+        self.exception("anyhow::Error", &message, report::anyhow_frames(error))
+    }
+
+    #[cfg(feature = "eyre")]
+    pub fn eyre(&mut self, report: &eyre::Report) -> &mut Self {
+        let message = error_chain(&**report);
+
+        self.exception("eyre::Report", &message, report::eyre_frames(report))
+    }
+
+    fn exception(&mut self, class: &str, message: &str, frames: Vec<trace::RayFrame>) -> &mut Self {
+        let message = RayMessage::Exception(RayException {
+            class: class.to_string(),
+            message: message.to_string(),
+            frames: frames.into_iter().map(RayTraceFrame::from).collect(),
+        });
+
+        let content = RayContent {
+            content_type: RayException::get_type(),
+            origin: RayOrigin::new(),
+            content: message,
+        };
+
+        self.request.payloads.push(content);
+
+        self.send();
+        self
+    }
+
+    // Unnamed counters are keyed by the call site, so each `count(None)` line gets its own counter
+    #[track_caller]
+    pub fn count(&mut self, name: Option<&str>) -> &mut Self {
+        let location = std::panic::Location::caller();
+
+        let key = match name {
+            Some(name) => name.to_string(),
+            None => format!("{}:{}", location.file(), location.line()),
+        };
+
+        let counter = counters::increment(&key);
+
+        let times = match counter.times {
+            1 => "1 time".to_string(),
+            times => format!("{} times", times),
+        };
+
+        let value = match name {
+            Some(name) => format!("Called {} {}.", name, times),
+            None => format!("Called {}.", times),
+        };
+
+        // Reuse the counter's uuid so Ray updates the existing entry rather than adding a new one
+        self.request.uuid = counter.uuid;
+        self.request.payloads.clear();
+
+        let message = RayMessage::Custom(RayCustom {
+            content: value,
+            label: "".to_string(),
+        });
+
+        let content = RayContent {
+            content_type: RayCustom::get_type(),
+            origin: RayOrigin::new(),
+            content: message,
+        };
+
+        self.request.payloads.push(content);
+
+        self.send();
+        self
+    }
+
+    pub fn counter_value(&self, name: &str) -> u32 {
+        counters::value(name)
     }
 
     pub fn clear_counters(&mut self) -> &mut Self {
-        unimplemented!();
+        counters::clear();
+
+        self
     }
 
     pub fn disable(&mut self) -> &mut Self {
@@ -349,81 +676,451 @@ impl Ray {
         self.is_enabled
     }
 
-    pub fn file(&mut self) -> &mut Self {
-        unimplemented!();
+    pub fn file<P: AsRef<std::path::Path>>(&mut self, path: P) -> &mut Self {
+        self.file_with(path, RayFileOptions::default())
+    }
+
+    pub fn file_with<P: AsRef<std::path::Path>>(
+        &mut self,
+        path: P,
+        options: RayFileOptions,
+    ) -> &mut Self {
+        let path = path.as_ref();
+
+        let html = match file::file_html(path, &options) {
+            Ok(html) => html,
+            Err(error) => {
+                return self.text(&format!("Couldn't read file {}: {}", path.display(), error))
+            }
+        };
+
+        // Point the origin at the file instead of the caller, so clicking it opens the file
+        let mut origin = RayOrigin::new();
+
+        origin.file = paths::map(
+            &std::fs::canonicalize(path)
+                .unwrap_or_else(|_| path.to_path_buf())
+                .display()
+                .to_string(),
+        );
+        origin.line_number = options
+            .highlight
+            .or(options.lines.map(|(first, _)| first))
+            .unwrap_or(1) as u32;
+        origin.resolved = true;
+
+        let message = RayMessage::FileContents(RayFileContents {
+            label: RayMessageType::File,
+            content: html,
+        });
+
+        let content = RayContent {
+            content_type: RayFileContents::get_type(),
+            origin,
+            content: message,
+        };
+
+        self.request.payloads.push(content);
+
+        self.send();
+        self
+    }
+
+    pub fn blue(&mut self) -> &mut Self {
+        self.color(RayColor::Blue)
     }
 
     pub fn gray(&mut self) -> &mut Self {
-        unimplemented!();
+        self.color(RayColor::Gray)
     }
 
     pub fn green(&mut self) -> &mut Self {
-        unimplemented!();
+        self.color(RayColor::Green)
     }
 
     pub fn hide(&mut self) -> &mut Self {
-        unimplemented!();
+        let message = RayMessage::Hide(RayHide {});
+
+        let content = RayContent {
+            content_type: RayHide::get_type(),
+            origin: RayOrigin::new(),
+            content: message,
+        };
+
+        self.request.payloads.push(content);
+
+        self.send();
+        self
     }
 
     pub fn hide_app(&mut self) -> &mut Self {
-        unimplemented!();
+        let message = RayMessage::HideApp(RayHideApp {});
+
+        let content = RayContent {
+            content_type: RayHideApp::get_type(),
+            origin: RayOrigin::new(),
+            content: message,
+        };
+
+        self.send_alone(content)
     }
 
-    pub fn image(&mut self) -> &mut Self {
-        unimplemented!();
+    pub fn image<I: Into<RayImage>>(&mut self, image: I) -> &mut Self {
+        self.image_with(image, RayImageOptions::default())
     }
 
-    // I'm not sure if this is possible in Rust
-    pub fn r#if(&mut self) -> &mut Self {
-        unimplemented!();
+    // Images that can't be read or are over the size limit are reported as text instead
+    pub fn image_with<I: Into<RayImage>>(
+        &mut self,
+        image: I,
+        options: RayImageOptions,
+    ) -> &mut Self {
+        match image::image_html(&image.into(), &options) {
+            Ok(html) => self.image_payload(html),
+            Err(error) => self.text(&error),
+        }
     }
 
-    pub fn json(&mut self) -> &mut Self {
-        unimplemented!();
+    pub fn pixels<B: RayPixelData + ?Sized>(
+        &mut self,
+        buffer: &B,
+        width: u32,
+        height: u32,
+        format: RayPixelFormat,
+    ) -> &mut Self {
+        self.compare_pixels(&[RayPixels::new(buffer, width, height, format)])
     }
 
-    pub fn label(&mut self) -> &mut Self {
-        unimplemented!();
+    // Shows the buffers next to each other, like the steps of an image pipeline
+    pub fn compare_pixels(&mut self, images: &[RayPixels]) -> &mut Self {
+        match pixels::pixels_html(images) {
+            Ok(html) => self.image_payload(html),
+            Err(error) => self.text(&error),
+        }
+    }
+
+    fn image_payload(&mut self, html: String) -> &mut Self {
+        let message = RayMessage::Image(RayImagePayload {
+            label: RayMessageType::Image,
+            content: html,
+        });
+
+        let content = RayContent {
+            content_type: RayImagePayload::get_type(),
+            origin: RayOrigin::new(),
+            content: message,
+        };
+
+        self.request.payloads.push(content);
+
+        self.send();
+        self
+    }
+
+    // Invalid JSON is shown as HTML pointing at where parsing failed instead
+    pub fn json(&mut self, value: &str) -> &mut Self {
+        let pretty = match json::pretty(value) {
+            Ok(pretty) => pretty,
+            Err(error) => return self.html(&json::error_html(value, &error)),
+        };
+
+        let message = RayMessage::JsonString(RayJsonString { value: pretty });
+
+        let content = RayContent {
+            content_type: RayJsonString::get_type(),
+            origin: RayOrigin::new(),
+            content: message,
+        };
+
+        self.request.payloads.push(content);
+
+        self.send();
+        self
+    }
+
+    pub fn label(&mut self, label: &str) -> &mut Self {
+        let message = RayMessage::Label(RayLabel {
+            label: label.to_string(),
+        });
+
+        let content = RayContent {
+            content_type: RayLabel::get_type(),
+            origin: RayOrigin::new(),
+            content: message,
+        };
+
+        self.request.payloads.push(content);
+
+        self.send();
+        self
+    }
+
+    pub fn size(&mut self, size: RaySize) -> &mut Self {
+        let message = RayMessage::Size(RaySizePayload { size });
+
+        let content = RayContent {
+            content_type: RaySizePayload::get_type(),
+            origin: RayOrigin::new(),
+            content: message,
+        };
+
+        self.request.payloads.push(content);
+
+        self.send();
+        self
     }
 
     pub fn large(&mut self) -> &mut Self {
-        unimplemented!();
+        self.size(RaySize::Large)
     }
 
     pub fn limit(&mut self) -> &mut Self {
         unimplemented!();
     }
 
-    pub fn link(&mut self) -> &mut Self {
-        unimplemented!();
+    // Invalid URLs are reported as text instead of sending a link that goes nowhere
+    pub fn link(&mut self, url: &str, label: Option<&str>) -> &mut Self {
+        match link::link_html(url, label) {
+            Ok(html) => self.html(&html),
+            Err(error) => self.text(&error),
+        }
     }
 
-    pub fn measure(&mut self) -> &mut Self {
-        unimplemented!();
+    // The first call starts the stopwatch, every call after that reports the time since it started
+    // and since the previous call
+    pub fn measure(&mut self, name: Option<&str>) -> &mut Self {
+        let name = name.unwrap_or("default");
+        let lap = stopwatches::lap(name);
+
+        let message = RayMessage::Measure(RayMeasure {
+            name: name.to_string(),
+            is_new_timer: lap.is_new_timer,
+            total_time: lap.total_time.as_secs_f64() * 1000.0,
+            max_memory_usage_during_total_time: lap.max_memory_usage,
+            time_since_last_call: lap.time_since_last_call.as_secs_f64() * 1000.0,
+            max_memory_usage_since_last_call: lap.max_memory_usage_since_last_call,
+            memory_usage: lap.memory_usage,
+            memory_delta: lap.memory_delta,
+        });
+
+        let content = RayContent {
+            content_type: RayMeasure::get_type(),
+            origin: RayOrigin::new(),
+            content: message,
+        };
+
+        self.request.payloads.push(content);
+
+        self.send();
+        self
     }
 
-    pub fn notify(&mut self) -> &mut Self {
-        unimplemented!();
+    pub fn measure_fn<T, F: FnOnce() -> T>(&mut self, callback: F) -> T {
+        let memory = stopwatches::memory_usage();
+        let started_at = std::time::Instant::now();
+
+        let result = callback();
+
+        let elapsed = started_at.elapsed().as_secs_f64() * 1000.0;
+        let memory_after = stopwatches::memory_usage();
+
+        let message = RayMessage::Measure(RayMeasure {
+            name: "closure".to_string(),
+            is_new_timer: false,
+            total_time: elapsed,
+            max_memory_usage_during_total_time: memory.max(memory_after),
+            time_since_last_call: elapsed,
+            max_memory_usage_since_last_call: memory.max(memory_after),
+            memory_usage: memory_after,
+            memory_delta: memory_after as i64 - memory as i64,
+        });
+
+        let content = RayContent {
+            content_type: RayMeasure::get_type(),
+            origin: RayOrigin::new(),
+            content: message,
+        };
+
+        self.request.payloads.push(content);
+
+        self.send();
+
+        result
+    }
+
+    // Shows a desktop notification through the Ray app
+    pub fn notify(&mut self, text: &str) -> &mut Self {
+        let message = RayMessage::Notify(RayNotify {
+            value: text.to_string(),
+        });
+
+        let content = RayContent {
+            content_type: RayNotify::get_type(),
+            origin: RayOrigin::new(),
+            content: message,
+        };
+
+        self.send_alone(content)
     }
 
     pub fn orange(&mut self) -> &mut Self {
-        unimplemented!();
+        self.color(RayColor::Orange)
     }
 
-    pub fn pass(&mut self) -> &mut Self {
-        unimplemented!();
+    // Sends the value and hands it back, so it can sit in the middle of a chain: .map(Ray::pass)
+    pub fn pass<T: std::fmt::Debug>(value: T) -> T {
+        Ray::new().log(vec![debug_to_html(&format!("{:#?}", value))]);
+
+        value
     }
 
     pub fn pause(&mut self) -> &mut Self {
-        unimplemented!();
+        self.pause_with(RayPauseOptions::default())
+    }
+
+    // Pausing creates a lock in Ray and waits until the lock is released from the app. This blocks
+    // the calling thread on every transport, use pause_async to wait without blocking a runtime
+    // https://github.com/spatie/ray/blob/main/src/Ray.php#L535
+    pub fn pause_with(&mut self, options: RayPauseOptions) -> &mut Self {
+        if !self.is_enabled {
+            return self;
+        }
+
+        let name = self.create_lock();
+
+        // send only spawns the request with tokio, the lock has to exist before we start polling
+        self.flush();
+
+        let started_at = std::time::Instant::now();
+
+        loop {
+            std::thread::sleep(options.poll_interval);
+
+            let status = lock_status(&name);
+
+            if self.lock_released(&name, &status, &options, started_at) {
+                break;
+            }
+        }
+
+        self.remove_lock(&name);
+
+        self
+    }
+
+    #[cfg(feature = "with_tokio")]
+    pub async fn pause_async(&mut self) -> &mut Self {
+        self.pause_async_with(RayPauseOptions::default()).await
+    }
+
+    #[cfg(feature = "with_tokio")]
+    pub async fn pause_async_with(&mut self, options: RayPauseOptions) -> &mut Self {
+        if !self.is_enabled {
+            return self;
+        }
+
+        let name = self.create_lock();
+        let request = self.outgoing();
+        let client = reqwest::Client::new();
+
+        // Unlike send this has to land before we start polling, so await it rather than spawning
+        let _ = client.post(RAY_URL).json(&request).send().await;
+
+        let started_at = std::time::Instant::now();
+
+        loop {
+            tokio::time::sleep(options.poll_interval).await;
+
+            let status = match client
+                .get(format!("{}/locks/{}", RAY_URL, name))
+                .send()
+                .await
+            {
+                Ok(response) => response.json::<RayLockStatus>().await.unwrap_or_default(),
+                Err(_) => RayLockStatus::default(),
+            };
+
+            if self.lock_released(&name, &status, &options, started_at) {
+                break;
+            }
+        }
+
+        self.remove_lock(&name);
+
+        self
+    }
+
+    fn create_lock(&mut self) -> String {
+        let name = uuid::Uuid::new_v4().simple().to_string();
+
+        let message = RayMessage::CreateLock(RayCreateLock { name: name.clone() });
+
+        let content = RayContent {
+            content_type: RayCreateLock::get_type(),
+            origin: RayOrigin::new(),
+            content: message,
+        };
+
+        self.request.payloads.push(content);
+
+        name
+    }
+
+    // The lock is done with, later calls on this instance shouldn't post it to Ray again
+    fn remove_lock(&mut self, name: &str) {
+        self.request.payloads.retain(|payload| {
+            !matches!(&payload.content, RayMessage::CreateLock(lock) if lock.name == name)
+        });
+    }
+
+    // If Ray can't be reached the lock reads as inactive, so we never hang when the app is closed
+    fn lock_released(
+        &mut self,
+        name: &str,
+        status: &RayLockStatus,
+        options: &RayPauseOptions,
+        started_at: std::time::Instant,
+    ) -> bool {
+        if status.stop_execution && options.stop_execution {
+            // die flushes what's left on this instance, which mustn't include the lock
+            self.remove_lock(name);
+            self.die(0);
+        }
+
+        if !status.active {
+            return true;
+        }
+
+        options
+            .timeout
+            .is_some_and(|timeout| started_at.elapsed() >= timeout)
     }
 
     pub fn info(&mut self) -> &mut Self {
-        unimplemented!();
+        self.info_with(&info::DEFAULT_ENV)
+    }
+
+    // Like info, but showing these environment variables instead of the default ones
+    pub fn info_with(&mut self, env: &[&str]) -> &mut Self {
+        let message = RayMessage::Table(RayTable {
+            values: info::info_values(&self.request.meta, env),
+            label: "Info".to_string(),
+        });
+
+        let content = RayContent {
+            content_type: RayTable::get_type(),
+            origin: RayOrigin::new(),
+            content: message,
+        };
+
+        self.request.payloads.push(content);
+
+        self.send();
+        self
     }
 
     pub fn purple(&mut self) -> &mut Self {
-        unimplemented!();
+        self.color(RayColor::Purple)
     }
 
     // TODO: This has 3 functions max, per_second and clear
@@ -432,38 +1129,305 @@ impl Ray {
     }
 
     pub fn red(&mut self) -> &mut Self {
-        unimplemented!();
+        self.color(RayColor::Red)
+    }
+
+    pub fn remove(&mut self) -> &mut Self {
+        let message = RayMessage::Remove(RayRemove {});
+
+        let content = RayContent {
+            content_type: RayRemove::get_type(),
+            origin: RayOrigin::new(),
+            content: message,
+        };
+
+        self.request.payloads.push(content);
+
+        self.send();
+        self
+    }
+
+    pub fn remove_when<C: RayCondition>(&mut self, condition: C) -> &mut Self {
+        if condition.evaluate() {
+            self.remove();
+        }
+
+        self
+    }
+
+    pub fn show_when<C: RayCondition>(&mut self, condition: C) -> &mut Self {
+        if !condition.evaluate() {
+            self.remove();
+        }
+
+        self
     }
 
     pub fn separator(&mut self) -> &mut Self {
-        unimplemented!();
+        let message = RayMessage::Separator(RaySeparator {});
+
+        let content = RayContent {
+            content_type: RaySeparator::get_type(),
+            origin: RayOrigin::new(),
+            content: message,
+        };
+
+        self.request.payloads.push(content);
+
+        self.send();
+        self
+    }
+
+    // Sends a header, runs f with everything it sends in the same colour, then closes the group
+    // with a separator. Groups can be nested, the innermost one's colour wins
+    pub fn group<T, F: FnOnce() -> T>(name: &str, f: F) -> T {
+        let value = {
+            let _group = groups::enter(groups::next_color());
+
+            Ray::new().text(name).large();
+
+            f()
+        };
+
+        Ray::new().separator();
+
+        value
     }
 
     pub fn show_app(&mut self) -> &mut Self {
-        unimplemented!();
+        let message = RayMessage::ShowApp(RayShowApp {});
+
+        let content = RayContent {
+            content_type: RayShowApp::get_type(),
+            origin: RayOrigin::new(),
+            content: message,
+        };
+
+        self.send_alone(content)
     }
 
     pub fn small(&mut self) -> &mut Self {
-        unimplemented!();
+        self.size(RaySize::Small)
     }
 
-    pub fn table(&mut self) -> &mut Self {
-        unimplemented!();
+    // Maps and structs become key/value rows, sequences of records become a grid with the field
+    // names as headers
+    pub fn table<T: Serialize + ?Sized>(&mut self, value: &T, label: Option<&str>) -> &mut Self {
+        let values = match serde_dumper::to_node(value) {
+            Ok(node) => table::table_values(node),
+            Err(error) => RayTableValues(vec![(
+                "error".to_string(),
+                format!("Failed to serialize value: {}", error),
+            )]),
+        };
+
+        let message = RayMessage::Table(RayTable {
+            values,
+            label: label.unwrap_or("Table").to_string(),
+        });
+
+        let content = RayContent {
+            content_type: RayTable::get_type(),
+            origin: RayOrigin::new(),
+            content: message,
+        };
+
+        self.request.payloads.push(content);
+
+        self.send();
+        self
     }
 
-    pub fn to_json(&mut self) -> &mut Self {
-        unimplemented!();
+    pub fn table_rows<I, T>(&mut self, rows: I, label: Option<&str>) -> &mut Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Serialize,
+    {
+        let rows: Vec<T> = rows.into_iter().collect();
+
+        self.table(&rows, label)
+    }
+
+    pub fn to_json<T: Serialize + ?Sized>(&mut self, value: &T) -> &mut Self {
+        match serde_json::to_string_pretty(value) {
+            Ok(json) => self.json(&json),
+            Err(error) => self.text(&format!("Failed to serialize value: {}", error)),
+        }
+    }
+
+    // Shows who called the function that called this, the first frame is the current function
+    pub fn caller(&mut self) -> &mut Self {
+        let frame = match trace::capture().into_iter().nth(1) {
+            Some(frame) => RayTraceFrame::from(frame),
+            None => RayTraceFrame::from(trace::RayFrame::default()),
+        };
+
+        let message = RayMessage::Caller(RayCaller { frame });
+
+        let content = RayContent {
+            content_type: RayCaller::get_type(),
+            origin: RayOrigin::new(),
+            content: message,
+        };
+
+        self.request.payloads.push(content);
+
+        self.send();
+        self
     }
 
     pub fn trace(&mut self) -> &mut Self {
-        unimplemented!();
+        self.trace_with(RayTraceOptions::default())
     }
 
-    pub fn url(&mut self) -> &mut Self {
-        unimplemented!();
+    pub fn trace_with(&mut self, options: RayTraceOptions) -> &mut Self {
+        let frames = trace::capture()
+            .into_iter()
+            .skip(options.offset)
+            .take(options.limit.unwrap_or(usize::MAX))
+            .map(RayTraceFrame::from)
+            .collect();
+
+        let message = RayMessage::Trace(RayTrace { frames });
+
+        let content = RayContent {
+            content_type: RayTrace::get_type(),
+            origin: RayOrigin::new(),
+            content: message,
+        };
+
+        self.request.payloads.push(content);
+
+        self.send();
+        self
     }
 
-    pub fn xml(&mut self) -> &mut Self {
-        unimplemented!();
+    pub fn url(&mut self, url: &str) -> &mut Self {
+        self.link(url, None)
+    }
+
+    // Malformed XML is shown as HTML pointing at the first error instead
+    pub fn xml(&mut self, value: &str) -> &mut Self {
+        let formatted = match xml::pretty_html(value) {
+            Ok(formatted) => formatted,
+            Err(error) => {
+                return self.html(&dumper::error_snippet_html(
+                    "Malformed XML",
+                    value,
+                    error.line,
+                    error.column,
+                    &error.message,
+                ))
+            }
+        };
+
+        let message = RayMessage::Xml(RayXml {
+            label: RayMessageType::Xml,
+            content: formatted,
+        });
+
+        let content = RayContent {
+            content_type: RayXml::get_type(),
+            origin: RayOrigin::new(),
+            content: message,
+        };
+
+        self.request.payloads.push(content);
+
+        self.send();
+        self
     }
 }
+
+// Ray shows this as the class of an error. type_name only sees `dyn Error` behind a trait object,
+// so the common std errors are found by downcasting and anything else gets a neutral label
+pub trait RayErrorClass {
+    fn error_class(&self) -> String;
+}
+
+impl<E: std::error::Error> RayErrorClass for E {
+    fn error_class(&self) -> String {
+        std::any::type_name::<E>().to_string()
+    }
+}
+
+impl RayErrorClass for dyn std::error::Error + 'static {
+    fn error_class(&self) -> String {
+        std_error_class(self)
+    }
+}
+
+impl RayErrorClass for dyn std::error::Error + Send + 'static {
+    fn error_class(&self) -> String {
+        std_error_class(self)
+    }
+}
+
+impl RayErrorClass for dyn std::error::Error + Send + Sync + 'static {
+    fn error_class(&self) -> String {
+        std_error_class(self)
+    }
+}
+
+fn std_error_class(error: &(dyn std::error::Error + 'static)) -> String {
+    macro_rules! downcast {
+        ($($error:ty),*) => {
+            $(
+                if error.is::<$error>() {
+                    return std::any::type_name::<$error>().to_string();
+                }
+            )*
+        };
+    }
+
+    downcast!(
+        std::io::Error,
+        std::fmt::Error,
+        std::num::ParseIntError,
+        std::num::ParseFloatError,
+        std::num::TryFromIntError,
+        std::str::ParseBoolError,
+        std::str::Utf8Error,
+        std::string::FromUtf8Error,
+        std::char::ParseCharError,
+        std::net::AddrParseError,
+        std::env::VarError,
+        std::time::SystemTimeError
+    );
+
+    "Error".to_string()
+}
+
+// reqwest's blocking client can't run on a runtime thread, so like flush this asks from a plain
+// thread
+fn lock_status(name: &str) -> RayLockStatus {
+    let url = format!("{}/locks/{}", RAY_URL, name);
+
+    std::thread::spawn(move || match reqwest::blocking::get(url) {
+        Ok(response) => response.json::<RayLockStatus>().unwrap_or_default(),
+        Err(_) => RayLockStatus::default(),
+    })
+    .join()
+    .unwrap_or_default()
+}
+
+// The error's Display output followed by every source() below it, the same layout anyhow uses
+fn error_chain<E: std::error::Error + ?Sized>(error: &E) -> String {
+    let mut message = error.to_string();
+    let mut source = error.source();
+
+    if source.is_some() {
+        message.push_str("\n\nCaused by:");
+    }
+
+    let mut index = 0;
+
+    while let Some(cause) = source {
+        message.push_str(&format!("\n    {}: {}", index, cause));
+
+        index += 1;
+        source = cause.source();
+    }
+
+    message
+}
@@ -0,0 +1,145 @@
+// Helpers for Ray::link and Ray::url. There's no link payload in Ray, the PHP package sends an
+// anchor as HTML and so do we, after checking the URL is something Ray can actually open.
+
+use crate::dumper::escape_html;
+use crate::paths;
+
+// Besides http, https and file, only schemes that open an editor are linked. Anything else, like
+// javascript: or data:, would run or show whatever the URL says when it's clicked in Ray
+const EDITOR_SCHEMES: &[&str] = &[
+    "atom",
+    "cursor",
+    "idea",
+    "macvim",
+    "nova",
+    "phpstorm",
+    "subl",
+    "txmt",
+    "vscode",
+    "vscode-insiders",
+    "vscodium",
+    "windsurf",
+    "zed",
+];
+
+// Builds the anchor Ray shows, or says what's wrong with the URL
+pub fn link_html(url: &str, label: Option<&str>) -> Result<String, String> {
+    let href = href(url)?;
+    let label = label
+        .filter(|label| !label.is_empty())
+        .unwrap_or(url.trim());
+
+    Ok(format!(
+        "<a href=\"{}\">{}</a>",
+        escape_html(&href),
+        escape_html(label)
+    ))
+}
+
+// Checks the URL and fills in what's missing: https:// for bare domains, and the path mapping
+// for file:// links
+pub fn href(url: &str) -> Result<String, String> {
+    let url = url.trim();
+
+    if url.is_empty() {
+        return Err("URL is empty".to_string());
+    }
+
+    if let Some(path) = url.strip_prefix("file://") {
+        if !path.starts_with('/') {
+            return Err(format!("{} isn't an absolute file path", url));
+        }
+
+        return Ok(format!("file://{}", encode_path(&paths::map(path))));
+    }
+
+    if url.chars().any(|c| c.is_whitespace() || c.is_control()) {
+        return Err(format!("URL {} can't contain whitespace", url));
+    }
+
+    let (scheme, rest) = match url.split_once("://") {
+        Some((scheme, rest)) if is_scheme(scheme) => (scheme.to_ascii_lowercase(), rest),
+        Some(_) => return Err(format!("URL {} has an invalid scheme", url)),
+        // Like the PHP package, example.com means https://example.com
+        None => ("https".to_string(), url),
+    };
+
+    if scheme != "http" && scheme != "https" && !EDITOR_SCHEMES.contains(&scheme.as_str()) {
+        return Err(format!("URL {} uses a scheme Ray shouldn't open", url));
+    }
+
+    let host = rest
+        .split(['/', '?', '#'])
+        .next()
+        .unwrap_or_default()
+        .rsplit('@')
+        .next()
+        .unwrap_or_default();
+
+    if (scheme == "http" || scheme == "https") && !is_host(host) {
+        return Err(format!("URL {} doesn't have a valid host", url));
+    }
+
+    if rest.is_empty() {
+        return Err(format!(
+            "URL {} is missing everything after the scheme",
+            url
+        ));
+    }
+
+    Ok(format!("{}://{}", scheme, rest))
+}
+
+fn is_scheme(scheme: &str) -> bool {
+    let mut chars = scheme.chars();
+
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+}
+
+// A domain, an IPv4 address or a bracketed IPv6 address, with an optional port
+fn is_host(host: &str) -> bool {
+    if let Some(ipv6) = host.strip_prefix('[') {
+        return ipv6.split_once(']').is_some_and(|(address, port)| {
+            !address.is_empty()
+                && address.chars().all(|c| c.is_ascii_hexdigit() || c == ':')
+                && (port.is_empty() || is_port(port))
+        });
+    }
+
+    let (name, port) = match host.split_once(':') {
+        Some((name, port)) => (name, Some(port)),
+        None => (host, None),
+    };
+
+    !name.is_empty()
+        && name.split('.').all(|label| {
+            !label.is_empty()
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        })
+        && port.map_or(true, |port| is_port(&format!(":{}", port)))
+}
+
+fn is_port(port: &str) -> bool {
+    port.strip_prefix(':')
+        .is_some_and(|port| port.parse::<u16>().is_ok())
+}
+
+// Spaces and the characters that would end the path early can't go in a URL as they are
+fn encode_path(path: &str) -> String {
+    let mut encoded = String::with_capacity(path.len());
+
+    for c in path.chars() {
+        match c {
+            ' ' => encoded.push_str("%20"),
+            '#' => encoded.push_str("%23"),
+            '?' => encoded.push_str("%3F"),
+            '%' => encoded.push_str("%25"),
+            c => encoded.push(c),
+        }
+    }
+
+    encoded
+}
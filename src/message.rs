@@ -5,19 +5,44 @@ use serde::{Deserialize, Serialize};
 pub enum RayMessage {
     Log(RayLog),
     Text(RayText),
-    Color(RayColor),
-    HTML(RayHtml),
+    Color(RayColorPayload),
+    Html(RayHtml),
     ClearAll(RayClearAll),
     Confetti(RayConfetti),
     Charles(RayCharles),
     NewScreen(RayNewScreen),
+    Custom(RayCustom),
+    Measure(RayMeasure),
+    CreateLock(RayCreateLock),
+    Trace(RayTrace),
+    Caller(RayCaller),
+    Exception(RayException),
+    Label(RayLabel),
+    Size(RaySizePayload),
+    ScreenColor(RayScreenColor),
+    Hide(RayHide),
+    Remove(RayRemove),
+    ShowApp(RayShowApp),
+    HideApp(RayHideApp),
+    Notify(RayNotify),
+    Table(RayTable),
+    JsonString(RayJsonString),
+    Xml(RayXml),
+    Image(RayImagePayload),
+    FileContents(RayFileContents),
+    Separator(RaySeparator),
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub enum RayMessageType {
     Log,
     Text,
-    HTML,
+    #[serde(rename = "HTML")]
+    Html,
+    #[serde(rename = "XML")]
+    Xml,
+    Image,
+    File,
     ClearAll,
     Confetti,
     Charles,
@@ -32,18 +57,52 @@ pub enum RayContentType {
     ClearAll,
     Confetti,
     NewScreen,
+    Measure,
+    CreateLock,
+    Trace,
+    Caller,
+    Exception,
+    Label,
+    Size,
+    ScreenColor,
+    Hide,
+    Remove,
+    ShowApp,
+    HideApp,
+    Notify,
+    Table,
+    JsonString,
+    Separator,
 }
 
-impl RayContentType {
-    pub fn to_string(&self) -> String {
-        match self {
-            RayContentType::Log => "log".to_string(),
-            RayContentType::Custom => "custom".to_string(),
-            RayContentType::Color => "color".to_string(),
-            RayContentType::ClearAll => "clear_all".to_string(),
-            RayContentType::Confetti => "confetti".to_string(),
-            RayContentType::NewScreen => "new_screen".to_string(),
-        }
+impl std::fmt::Display for RayContentType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = match self {
+            RayContentType::Log => "log",
+            RayContentType::Custom => "custom",
+            RayContentType::Color => "color",
+            RayContentType::ClearAll => "clear_all",
+            RayContentType::Confetti => "confetti",
+            RayContentType::NewScreen => "new_screen",
+            RayContentType::Measure => "measure",
+            RayContentType::CreateLock => "create_lock",
+            RayContentType::Trace => "trace",
+            RayContentType::Caller => "caller",
+            RayContentType::Exception => "exception",
+            RayContentType::Label => "label",
+            RayContentType::Size => "size",
+            RayContentType::ScreenColor => "screen_color",
+            RayContentType::Hide => "hide",
+            RayContentType::Remove => "remove",
+            RayContentType::ShowApp => "show_app",
+            RayContentType::HideApp => "hide_app",
+            RayContentType::Notify => "notify",
+            RayContentType::Table => "table",
+            RayContentType::JsonString => "json_string",
+            RayContentType::Separator => "separator",
+        };
+
+        write!(f, "{}", value)
     }
 }
 
@@ -75,39 +134,259 @@ impl RayText {
 
 // https://github.com/spatie/ray/blob/main/src/Payloads/ColorPayload.php
 #[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct RayColor {
-    pub color: RayColors,
+pub struct RayColorPayload {
+    pub color: RayColor,
 }
 
-impl RayColor {
+impl RayColorPayload {
     pub fn get_type() -> String {
         RayContentType::Color.to_string()
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(untagged)]
-pub enum RayColors {
-    Green(String),
-    Orange(String),
-    Red(String),
-    Purple(String),
-    Blue(String),
-    Gray(String),
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RayColor {
+    Green,
+    Orange,
+    Red,
+    Purple,
+    Blue,
+    Gray,
 }
 
-impl RayColors {
-    pub fn from(value: String) -> Self {
+impl std::str::FromStr for RayColor {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
         match value.to_lowercase().as_str() {
-            "green" => RayColors::Green("green".to_string()),
-            "orange" => RayColors::Orange("orange".to_string()),
-            "red" => RayColors::Red("red".to_string()),
-            "purple" => RayColors::Purple("purple".to_string()),
-            "blue" => RayColors::Blue("blue".to_string()),
-            "gray" => RayColors::Gray("gray".to_string()),
-            "grey" => RayColors::Gray("gray".to_string()), // In case someone spells it the right way
-            _ => RayColors::Gray("gray".to_string()),
+            "green" => Ok(RayColor::Green),
+            "orange" => Ok(RayColor::Orange),
+            "red" => Ok(RayColor::Red),
+            "purple" => Ok(RayColor::Purple),
+            "blue" => Ok(RayColor::Blue),
+            "gray" => Ok(RayColor::Gray),
+            "grey" => Ok(RayColor::Gray), // In case someone spells it the right way
+            _ => Err(format!("unknown Ray color \"{}\"", value)),
+        }
+    }
+}
+
+// https://github.com/spatie/ray/blob/main/src/Payloads/ScreenColorPayload.php
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RayScreenColor {
+    pub color: RayColor,
+}
+
+impl RayScreenColor {
+    pub fn get_type() -> String {
+        RayContentType::ScreenColor.to_string()
+    }
+}
+
+// https://github.com/spatie/ray/blob/main/src/Payloads/LabelPayload.php
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RayLabel {
+    pub label: String,
+}
+
+impl RayLabel {
+    pub fn get_type() -> String {
+        RayContentType::Label.to_string()
+    }
+}
+
+// https://github.com/spatie/ray/blob/main/src/Payloads/SizePayload.php
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RaySizePayload {
+    pub size: RaySize,
+}
+
+impl RaySizePayload {
+    pub fn get_type() -> String {
+        RayContentType::Size.to_string()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum RaySize {
+    #[serde(rename = "lg")]
+    Large,
+    #[serde(rename = "sm")]
+    Small,
+}
+
+// https://github.com/spatie/ray/blob/main/src/Payloads/HidePayload.php
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RayHide {}
+
+impl RayHide {
+    pub fn get_type() -> String {
+        RayContentType::Hide.to_string()
+    }
+}
+
+// https://github.com/spatie/ray/blob/main/src/Payloads/RemovePayload.php
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RayRemove {}
+
+impl RayRemove {
+    pub fn get_type() -> String {
+        RayContentType::Remove.to_string()
+    }
+}
+
+// https://github.com/spatie/ray/blob/main/src/Payloads/ShowAppPayload.php
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RayShowApp {}
+
+impl RayShowApp {
+    pub fn get_type() -> String {
+        RayContentType::ShowApp.to_string()
+    }
+}
+
+// https://github.com/spatie/ray/blob/main/src/Payloads/SeparatorPayload.php
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RaySeparator {}
+
+impl RaySeparator {
+    pub fn get_type() -> String {
+        RayContentType::Separator.to_string()
+    }
+}
+
+// https://github.com/spatie/ray/blob/main/src/Payloads/HideAppPayload.php
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RayHideApp {}
+
+impl RayHideApp {
+    pub fn get_type() -> String {
+        RayContentType::HideApp.to_string()
+    }
+}
+
+// https://github.com/spatie/ray/blob/main/src/Payloads/NotifyPayload.php
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RayNotify {
+    pub value: String,
+}
+
+impl RayNotify {
+    pub fn get_type() -> String {
+        RayContentType::Notify.to_string()
+    }
+}
+
+// https://github.com/spatie/ray/blob/main/src/Payloads/TablePayload.php
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RayTable {
+    pub values: RayTableValues,
+    pub label: String,
+}
+
+impl RayTable {
+    pub fn get_type() -> String {
+        RayContentType::Table.to_string()
+    }
+}
+
+// Table rows have to stay in the order they were added, which a HashMap wouldn't do, so this is
+// a list of pairs that goes over the wire as a JSON object
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RayTableValues(pub Vec<(String, String)>);
+
+impl Serialize for RayTableValues {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeMap;
+
+        let mut map = serializer.serialize_map(Some(self.0.len()))?;
+
+        for (key, value) in &self.0 {
+            map.serialize_entry(key, value)?;
+        }
+
+        map.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for RayTableValues {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct Visitor;
+
+        impl<'de> serde::de::Visitor<'de> for Visitor {
+            type Value = RayTableValues;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                write!(f, "a map of table rows")
+            }
+
+            fn visit_map<A: serde::de::MapAccess<'de>>(
+                self,
+                mut map: A,
+            ) -> Result<RayTableValues, A::Error> {
+                let mut values = Vec::new();
+
+                while let Some(entry) = map.next_entry()? {
+                    values.push(entry);
+                }
+
+                Ok(RayTableValues(values))
+            }
         }
+
+        deserializer.deserialize_map(Visitor)
+    }
+}
+
+// https://github.com/spatie/ray/blob/main/src/Payloads/JsonStringPayload.php
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RayJsonString {
+    pub value: String,
+}
+
+impl RayJsonString {
+    pub fn get_type() -> String {
+        RayContentType::JsonString.to_string()
+    }
+}
+
+// https://github.com/spatie/ray/blob/main/src/Payloads/XmlPayload.php
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RayXml {
+    pub label: RayMessageType,
+    pub content: String,
+}
+
+impl RayXml {
+    pub fn get_type() -> String {
+        RayContentType::Custom.to_string()
+    }
+}
+
+// https://github.com/spatie/ray/blob/main/src/Payloads/ImagePayload.php
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RayImagePayload {
+    pub label: RayMessageType,
+    pub content: String,
+}
+
+impl RayImagePayload {
+    pub fn get_type() -> String {
+        RayContentType::Custom.to_string()
+    }
+}
+
+// https://github.com/spatie/ray/blob/main/src/Payloads/FileContentsPayload.php
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RayFileContents {
+    pub label: RayMessageType,
+    pub content: String,
+}
+
+impl RayFileContents {
+    pub fn get_type() -> String {
+        RayContentType::Custom.to_string()
     }
 }
 
@@ -172,3 +451,105 @@ impl RayNewScreen {
         RayContentType::NewScreen.to_string()
     }
 }
+
+// https://github.com/spatie/ray/blob/main/src/Payloads/CustomPayload.php
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RayCustom {
+    pub content: String,
+    pub label: String,
+}
+
+impl RayCustom {
+    pub fn get_type() -> String {
+        RayContentType::Custom.to_string()
+    }
+}
+
+// https://github.com/spatie/ray/blob/main/src/Payloads/MeasurePayload.php
+// Times are in milliseconds and memory in bytes, memory_usage and memory_delta are our own extras
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RayMeasure {
+    pub name: String,
+    pub is_new_timer: bool,
+    pub total_time: f64,
+    pub max_memory_usage_during_total_time: u64,
+    pub time_since_last_call: f64,
+    pub max_memory_usage_since_last_call: u64,
+    pub memory_usage: u64,
+    pub memory_delta: i64,
+}
+
+impl RayMeasure {
+    pub fn get_type() -> String {
+        RayContentType::Measure.to_string()
+    }
+}
+
+// https://github.com/spatie/ray/blob/main/src/Payloads/CreateLockPayload.php
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RayCreateLock {
+    pub name: String,
+}
+
+impl RayCreateLock {
+    pub fn get_type() -> String {
+        RayContentType::CreateLock.to_string()
+    }
+}
+
+// https://github.com/spatie/ray/blob/main/src/Payloads/TracePayload.php
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RayTrace {
+    pub frames: Vec<RayTraceFrame>,
+}
+
+impl RayTrace {
+    pub fn get_type() -> String {
+        RayContentType::Trace.to_string()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RayTraceFrame {
+    pub file_name: String,
+    pub line_number: u32,
+    pub class: String,
+    pub method: String,
+    pub vendor_frame: bool,
+}
+
+// https://github.com/spatie/ray/blob/main/src/Payloads/CallerPayload.php
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RayCaller {
+    pub frame: RayTraceFrame,
+}
+
+impl RayCaller {
+    pub fn get_type() -> String {
+        RayContentType::Caller.to_string()
+    }
+}
+
+// https://github.com/spatie/ray/blob/main/src/Payloads/ExceptionPayload.php
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RayException {
+    pub class: String,
+    pub message: String,
+    pub frames: Vec<RayTraceFrame>,
+}
+
+impl RayException {
+    pub fn get_type() -> String {
+        RayContentType::Exception.to_string()
+    }
+}
+
+// What Ray answers to GET /locks/{name}
+// https://github.com/spatie/ray/blob/main/src/Client.php
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct RayLockStatus {
+    #[serde(default)]
+    pub active: bool,
+    #[serde(default)]
+    pub stop_execution: bool,
+}
@@ -0,0 +1,72 @@
+use crate::message::*;
+use crate::{trace, Ray, RayContent, RayOrigin};
+use std::any::Any;
+
+// Reports every panic to Ray as a red exception, then hands it on to the hook that was installed
+// before, so the usual message still ends up on stderr
+pub fn install_panic_hook() {
+    let previous = std::panic::take_hook();
+
+    std::panic::set_hook(Box::new(move |info| {
+        let thread = std::thread::current();
+        let location = info
+            .location()
+            .map(|location| {
+                format!(
+                    "{}:{}:{}",
+                    location.file(),
+                    location.line(),
+                    location.column()
+                )
+            })
+            .unwrap_or_else(|| "<unknown>".to_string());
+
+        let message = panic_message(thread.name(), &location, info.payload());
+
+        let mut ray = Ray::new();
+
+        ray.request.payloads.push(RayContent {
+            content_type: RayException::get_type(),
+            origin: RayOrigin::new(),
+            content: RayMessage::Exception(RayException {
+                class: "panic".to_string(),
+                message,
+                frames: trace::capture()
+                    .into_iter()
+                    .map(RayTraceFrame::from)
+                    .collect(),
+            }),
+        });
+
+        ray.request.payloads.push(RayContent {
+            content_type: RayColorPayload::get_type(),
+            origin: RayOrigin::new(),
+            content: RayMessage::Color(RayColorPayload {
+                color: RayColor::Red,
+            }),
+        });
+
+        // The process may be about to abort, so this can't be left to a background task
+        ray.flush();
+
+        previous(info);
+    }));
+}
+
+// Same layout as the default hook: thread 'main' panicked at src/main.rs:2:5:\nmessage
+pub fn panic_message(thread: Option<&str>, location: &str, payload: &(dyn Any + Send)) -> String {
+    let message = if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "Box<dyn Any>".to_string()
+    };
+
+    format!(
+        "thread '{}' panicked at {}:\n{}",
+        thread.unwrap_or("<unnamed>"),
+        location,
+        message
+    )
+}
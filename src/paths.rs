@@ -0,0 +1,55 @@
+// Code running in a container or on another machine reports paths Ray can't open. A mapping
+// swaps the remote prefix for the local one, like the local_path and remote_path settings of the
+// PHP package. It's set with Ray::path_mapping, or the RAY_REMOTE_PATH and RAY_LOCAL_PATH
+// environment variables.
+
+use std::sync::RwLock;
+
+static MAPPING: RwLock<Option<(String, String)>> = RwLock::new(None);
+
+pub fn set(remote: &str, local: &str) {
+    let mapping = (
+        remote.trim_end_matches('/').to_string(),
+        local.trim_end_matches('/').to_string(),
+    );
+
+    *MAPPING.write().unwrap_or_else(|error| error.into_inner()) = Some(mapping);
+}
+
+pub fn clear() {
+    *MAPPING.write().unwrap_or_else(|error| error.into_inner()) = None;
+}
+
+fn mapping() -> Option<(String, String)> {
+    if let Some(mapping) = MAPPING
+        .read()
+        .unwrap_or_else(|error| error.into_inner())
+        .clone()
+    {
+        return Some(mapping);
+    }
+
+    match (
+        std::env::var("RAY_REMOTE_PATH"),
+        std::env::var("RAY_LOCAL_PATH"),
+    ) {
+        (Ok(remote), Ok(local)) if !remote.is_empty() => Some((
+            remote.trim_end_matches('/').to_string(),
+            local.trim_end_matches('/').to_string(),
+        )),
+        _ => None,
+    }
+}
+
+// Rewrites a path under the remote prefix to the same path under the local one
+pub fn map(path: &str) -> String {
+    let Some((remote, local)) = mapping() else {
+        return path.to_string();
+    };
+
+    // Only whole directories match, /app shouldn't turn /application into /localication
+    match path.strip_prefix(&remote) {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => format!("{}{}", local, rest),
+        _ => path.to_string(),
+    }
+}
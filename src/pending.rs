@@ -0,0 +1,36 @@
+// With tokio, send hands the request to a blocking task and returns straight away. die exits the
+// process, which would kill those tasks mid-request, so every send is counted until it's done and
+// die waits for the count to reach zero first.
+
+use std::sync::{Condvar, Mutex, MutexGuard};
+
+static PENDING: Mutex<usize> = Mutex::new(0);
+static DONE: Condvar = Condvar::new();
+
+fn pending() -> MutexGuard<'static, usize> {
+    // The count is only ever changed in one step, so a poisoned lock still holds the right value
+    PENDING.lock().unwrap_or_else(|error| error.into_inner())
+}
+
+// Counts a send until it's dropped, even if the task never ran because spawning it panicked
+pub struct RayPendingGuard;
+
+pub fn start() -> RayPendingGuard {
+    *pending() += 1;
+
+    RayPendingGuard
+}
+
+impl Drop for RayPendingGuard {
+    fn drop(&mut self) {
+        *pending() -= 1;
+
+        DONE.notify_all();
+    }
+}
+
+pub fn wait() {
+    let _pending = DONE
+        .wait_while(pending(), |pending| *pending > 0)
+        .unwrap_or_else(|error| error.into_inner());
+}
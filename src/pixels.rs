@@ -0,0 +1,192 @@
+// Raw pixel buffers for Ray::pixels. Buffers are turned into 8 bit pixels as soon as they're
+// wrapped, so u8 and f32 buffers can be compared side by side.
+
+use crate::dumper::{escape_html, STYLE_CAPTION};
+use crate::image::{image_html, RayImage, RayImageOptions};
+use crate::png;
+
+const STYLE_ROW: &str = "display: flex; gap: 0.5em; align-items: flex-start; flex-wrap: wrap;";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RayPixelFormat {
+    Gray,
+    GrayAlpha,
+    Rgb,
+    Rgba,
+}
+
+impl RayPixelFormat {
+    pub fn channels(&self) -> usize {
+        match self {
+            RayPixelFormat::Gray => 1,
+            RayPixelFormat::GrayAlpha => 2,
+            RayPixelFormat::Rgb => 3,
+            RayPixelFormat::Rgba => 4,
+        }
+    }
+
+    fn has_alpha(&self) -> bool {
+        matches!(self, RayPixelFormat::GrayAlpha | RayPixelFormat::Rgba)
+    }
+
+    fn color_type(&self) -> u8 {
+        match self {
+            RayPixelFormat::Gray => 0,
+            RayPixelFormat::GrayAlpha => 4,
+            RayPixelFormat::Rgb => 2,
+            RayPixelFormat::Rgba => 6,
+        }
+    }
+}
+
+// Anything that can be turned into 8 bit pixels
+pub trait RayPixelData {
+    // The pixels, and for floats the range that was stretched to 0..=255
+    fn to_pixels(&self, format: RayPixelFormat) -> (Vec<u8>, Option<(f32, f32)>);
+}
+
+impl RayPixelData for [u8] {
+    fn to_pixels(&self, _format: RayPixelFormat) -> (Vec<u8>, Option<(f32, f32)>) {
+        (self.to_vec(), None)
+    }
+}
+
+impl RayPixelData for Vec<u8> {
+    fn to_pixels(&self, format: RayPixelFormat) -> (Vec<u8>, Option<(f32, f32)>) {
+        self.as_slice().to_pixels(format)
+    }
+}
+
+impl<const N: usize> RayPixelData for [u8; N] {
+    fn to_pixels(&self, format: RayPixelFormat) -> (Vec<u8>, Option<(f32, f32)>) {
+        self.as_slice().to_pixels(format)
+    }
+}
+
+// Colour channels are stretched from the smallest to the largest finite value, so a buffer of
+// gradients or distances is visible whatever its range. Alpha is always read as 0.0..=1.0.
+impl RayPixelData for [f32] {
+    fn to_pixels(&self, format: RayPixelFormat) -> (Vec<u8>, Option<(f32, f32)>) {
+        let channels = format.channels();
+        let is_alpha = |index: usize| format.has_alpha() && index % channels == channels - 1;
+
+        let (min, max) = self
+            .iter()
+            .enumerate()
+            .filter(|(index, value)| !is_alpha(*index) && value.is_finite())
+            .fold(
+                (f32::INFINITY, f32::NEG_INFINITY),
+                |(min, max), (_, &value)| (min.min(value), max.max(value)),
+            );
+
+        let pixels = self
+            .iter()
+            .enumerate()
+            .map(|(index, &value)| {
+                let value = match value {
+                    _ if !value.is_finite() => 0.0,
+                    _ if is_alpha(index) || max <= min => value.clamp(0.0, 1.0),
+                    _ => (value - min) / (max - min),
+                };
+
+                (value * 255.0).round() as u8
+            })
+            .collect();
+
+        let range = if min <= max { Some((min, max)) } else { None };
+
+        (pixels, range)
+    }
+}
+
+impl RayPixelData for Vec<f32> {
+    fn to_pixels(&self, format: RayPixelFormat) -> (Vec<u8>, Option<(f32, f32)>) {
+        self.as_slice().to_pixels(format)
+    }
+}
+
+impl<const N: usize> RayPixelData for [f32; N] {
+    fn to_pixels(&self, format: RayPixelFormat) -> (Vec<u8>, Option<(f32, f32)>) {
+        self.as_slice().to_pixels(format)
+    }
+}
+
+// A pixel buffer with its dimensions, for showing several of them side by side
+#[derive(Debug, Clone, PartialEq)]
+pub struct RayPixels {
+    pub width: u32,
+    pub height: u32,
+    pub format: RayPixelFormat,
+    pub pixels: Vec<u8>,
+    pub range: Option<(f32, f32)>,
+}
+
+impl RayPixels {
+    pub fn new<B: RayPixelData + ?Sized>(
+        buffer: &B,
+        width: u32,
+        height: u32,
+        format: RayPixelFormat,
+    ) -> Self {
+        let (pixels, range) = buffer.to_pixels(format);
+
+        Self {
+            width,
+            height,
+            format,
+            pixels,
+            range,
+        }
+    }
+
+    pub fn to_png(&self) -> Result<Vec<u8>, String> {
+        let expected = self.width as usize * self.height as usize * self.format.channels();
+
+        if self.pixels.len() != expected {
+            return Err(format!(
+                "Expected {} values for a {}×{} {:?} image but got {}",
+                expected,
+                self.width,
+                self.height,
+                self.format,
+                self.pixels.len()
+            ));
+        }
+
+        Ok(png::encode(
+            &self.pixels,
+            self.width,
+            self.height,
+            self.format.color_type(),
+            self.format.channels(),
+        ))
+    }
+}
+
+// Encodes each buffer and lays them out in a row, with the range floats were stretched from
+pub fn pixels_html(images: &[RayPixels]) -> Result<String, String> {
+    // Limits are for files and data someone else handed us, these were made in this process
+    let options = RayImageOptions {
+        max_bytes: None,
+        ..RayImageOptions::default()
+    };
+
+    let mut html = String::new();
+
+    for image in images {
+        html.push_str("<div>");
+        html.push_str(&image_html(&RayImage::Bytes(image.to_png()?), &options)?);
+
+        if let Some((min, max)) = image.range {
+            html.push_str(&format!(
+                "<div style=\"{}\">{}</div>",
+                STYLE_CAPTION,
+                escape_html(&format!("stretched from {} to {}", min, max))
+            ));
+        }
+
+        html.push_str("</div>");
+    }
+
+    Ok(format!("<div style=\"{}\">{}</div>", STYLE_ROW, html))
+}
@@ -0,0 +1,577 @@
+// A small PNG encoder for Ray::pixels, so showing a pixel buffer doesn't pull in an image crate.
+// It only writes 8 bit images and compresses with deflate's fixed Huffman codes and a greedy
+// LZ77 pass, which is plenty for the flat colours and gradients of debug images. The decoder is
+// there so Ray::image can scale big PNGs down before sending them.
+
+const SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+const WINDOW: usize = 32768;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+
+// Encodes 8 bit pixels, `color_type` is PNG's own: 0 gray, 2 RGB, 4 gray and alpha, 6 RGBA
+pub fn encode(pixels: &[u8], width: u32, height: u32, color_type: u8, channels: usize) -> Vec<u8> {
+    let mut ihdr = Vec::with_capacity(13);
+    ihdr.extend_from_slice(&width.to_be_bytes());
+    ihdr.extend_from_slice(&height.to_be_bytes());
+    ihdr.extend_from_slice(&[8, color_type, 0, 0, 0]);
+
+    // Every scanline starts with its filter type, 0 leaves the row as it is
+    let stride = width as usize * channels;
+    let mut raw = Vec::with_capacity((stride + 1) * height as usize);
+
+    for row in pixels.chunks(stride.max(1)).take(height as usize) {
+        raw.push(0);
+        raw.extend_from_slice(row);
+    }
+
+    let mut png = SIGNATURE.to_vec();
+    chunk(&mut png, b"IHDR", &ihdr);
+    chunk(&mut png, b"IDAT", &zlib(&raw));
+    chunk(&mut png, b"IEND", &[]);
+
+    png
+}
+
+fn chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+
+    let start = png.len();
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+
+    let crc = crc32(&png[start..]);
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+pub fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = 0xffff_ffffu32;
+
+    for &byte in bytes {
+        crc ^= byte as u32;
+
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                0xedb8_8320 ^ (crc >> 1)
+            } else {
+                crc >> 1
+            };
+        }
+    }
+
+    !crc
+}
+
+pub fn adler32(bytes: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+
+    for &byte in bytes {
+        a = (a + byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+
+    b << 16 | a
+}
+
+pub fn zlib(data: &[u8]) -> Vec<u8> {
+    // Deflate with a 32K window and no preset dictionary
+    let mut out = BitWriter::default();
+    out.bytes.extend_from_slice(&[0x78, 0x01]);
+
+    // A single final block using the fixed Huffman codes
+    out.write(1, 1);
+    out.write(1, 2);
+
+    let mut head = vec![usize::MAX; 1 << 15];
+    let hash = |at: usize| {
+        ((data[at] as usize) << 10 ^ (data[at + 1] as usize) << 5 ^ data[at + 2] as usize)
+            & ((1 << 15) - 1)
+    };
+
+    let mut i = 0;
+
+    while i < data.len() {
+        let mut length = 0;
+        let mut distance = 0;
+
+        if i + MIN_MATCH <= data.len() {
+            let h = hash(i);
+            let candidate = head[h];
+            head[h] = i;
+
+            if candidate != usize::MAX && i - candidate <= WINDOW {
+                let max = MAX_MATCH.min(data.len() - i);
+
+                while length < max && data[candidate + length] == data[i + length] {
+                    length += 1;
+                }
+
+                distance = i - candidate;
+            }
+        }
+
+        if length >= MIN_MATCH {
+            out.length(length, distance);
+
+            // Keep the hash table up to date for the bytes the match skipped over
+            for at in i + 1..(i + length).min(data.len().saturating_sub(MIN_MATCH - 1)) {
+                head[hash(at)] = at;
+            }
+
+            i += length;
+        } else {
+            out.symbol(data[i] as u16);
+            i += 1;
+        }
+    }
+
+    out.symbol(256);
+    out.flush();
+    out.bytes.extend_from_slice(&adler32(data).to_be_bytes());
+
+    out.bytes
+}
+
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u64,
+    bits: u32,
+}
+
+impl BitWriter {
+    // Values are packed least significant bit first
+    fn write(&mut self, value: u32, bits: u32) {
+        self.buffer |= (value as u64) << self.bits;
+        self.bits += bits;
+
+        while self.bits >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.bits -= 8;
+        }
+    }
+
+    // Huffman codes are packed most significant bit first, so they go in reversed
+    fn code(&mut self, code: u32, bits: u32) {
+        self.write(code.reverse_bits() >> (32 - bits), bits);
+    }
+
+    fn symbol(&mut self, symbol: u16) {
+        let symbol = symbol as u32;
+
+        match symbol {
+            0..=143 => self.code(0x30 + symbol, 8),
+            144..=255 => self.code(0x190 + symbol - 144, 9),
+            256..=279 => self.code(symbol - 256, 7),
+            _ => self.code(0xc0 + symbol - 280, 8),
+        }
+    }
+
+    fn length(&mut self, length: usize, distance: usize) {
+        let index = LENGTH_BASE
+            .iter()
+            .rposition(|&base| base as usize <= length)
+            .unwrap_or(0);
+
+        self.symbol(257 + index as u16);
+        self.write(
+            (length - LENGTH_BASE[index] as usize) as u32,
+            LENGTH_EXTRA[index] as u32,
+        );
+
+        let index = DISTANCE_BASE
+            .iter()
+            .rposition(|&base| base as usize <= distance)
+            .unwrap_or(0);
+
+        self.code(index as u32, 5);
+        self.write(
+            (distance - DISTANCE_BASE[index] as usize) as u32,
+            DISTANCE_EXTRA[index] as u32,
+        );
+    }
+
+    fn flush(&mut self) {
+        if self.bits > 0 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer = 0;
+            self.bits = 0;
+        }
+    }
+}
+
+// PNGs that are too big to decode in memory are left alone rather than scaled down
+const MAX_PIXELS: u64 = 64 * 1024 * 1024;
+
+// Decodes a PNG to 8 bit RGBA, along with its width and height. Every bit depth and colour type
+// is read, but interlaced images aren't and give None like anything that isn't a valid PNG
+pub fn decode(bytes: &[u8]) -> Option<(Vec<u8>, u32, u32)> {
+    let mut at = bytes.strip_prefix(SIGNATURE).map(|_| SIGNATURE.len())?;
+    let mut header = None;
+    let mut palette: &[u8] = &[];
+    let mut transparency: &[u8] = &[];
+    let mut compressed = Vec::new();
+
+    while at + 8 <= bytes.len() {
+        let length = u32::from_be_bytes(bytes[at..at + 4].try_into().ok()?) as usize;
+        let kind = &bytes[at + 4..at + 8];
+        let data = bytes.get(at + 8..(at + 8).checked_add(length)?)?;
+
+        match kind {
+            b"IHDR" if data.len() == 13 => header = Some(data),
+            b"PLTE" => palette = data,
+            b"tRNS" => transparency = data,
+            b"IDAT" => compressed.extend_from_slice(data),
+            b"IEND" => break,
+            _ => {}
+        }
+
+        // Skip the CRC as well
+        at += 12 + length;
+    }
+
+    let header = header?;
+    let width = u32::from_be_bytes(header[0..4].try_into().ok()?);
+    let height = u32::from_be_bytes(header[4..8].try_into().ok()?);
+    let (depth, color_type, interlace) = (header[8], header[9], header[12]);
+
+    let channels = match (color_type, depth) {
+        (0, 1 | 2 | 4 | 8 | 16) => 1,
+        (3, 1 | 2 | 4 | 8) => 1,
+        (2 | 4 | 6, 8 | 16) => [0, 0, 3, 0, 2, 0, 4][color_type as usize],
+        _ => return None,
+    };
+
+    if width == 0 || height == 0 || width as u64 * height as u64 > MAX_PIXELS || interlace != 0 {
+        return None;
+    }
+
+    let bits_per_pixel = channels * depth as usize;
+    let stride = (width as usize * bits_per_pixel).div_ceil(8);
+    // Filters look back a whole pixel, or a byte when pixels are smaller than that
+    let step = bits_per_pixel.div_ceil(8);
+
+    // Past the two byte zlib header, the Adler-32 at the end isn't checked
+    let raw = inflate(compressed.get(2..)?, (stride + 1) * height as usize)?;
+    let mut rows = vec![0u8; stride * height as usize];
+
+    for y in 0..height as usize {
+        let line = &raw[y * (stride + 1)..(y + 1) * (stride + 1)];
+        let (previous, current) = rows.split_at_mut(y * stride);
+        let above = previous
+            .get(previous.len().saturating_sub(stride)..)
+            .filter(|_| y > 0);
+        let row = &mut current[..stride];
+
+        for x in 0..stride {
+            let a = if x >= step { row[x - step] } else { 0 };
+            let b = above.map_or(0, |above| above[x]);
+            let c = match above {
+                Some(above) if x >= step => above[x - step],
+                _ => 0,
+            };
+
+            row[x] = line[1 + x].wrapping_add(match line[0] {
+                0 => 0,
+                1 => a,
+                2 => b,
+                3 => ((a as u16 + b as u16) / 2) as u8,
+                4 => paeth(a, b, c),
+                _ => return None,
+            });
+        }
+    }
+
+    let sample = |row: &[u8], index: usize| -> u8 {
+        match depth {
+            8 => row[index],
+            16 => row[index * 2],
+            _ => {
+                let per_byte = 8 / depth as usize;
+                let shift = 8 - depth as usize * (index % per_byte + 1);
+                let value = row[index / per_byte] >> shift & ((1 << depth) - 1);
+
+                // Palette indices stay as they are, gray levels are stretched to 0..=255
+                if color_type == 3 {
+                    value
+                } else {
+                    (value as u32 * 255 / ((1 << depth) - 1)) as u8
+                }
+            }
+        }
+    };
+
+    let mut rgba = Vec::with_capacity(width as usize * height as usize * 4);
+
+    for row in rows.chunks(stride) {
+        for x in 0..width as usize {
+            let value = |channel: usize| sample(row, x * channels + channel);
+
+            match color_type {
+                0 => rgba.extend_from_slice(&[value(0), value(0), value(0), 255]),
+                2 => rgba.extend_from_slice(&[value(0), value(1), value(2), 255]),
+                3 => {
+                    let index = value(0) as usize;
+                    let color = palette.get(index * 3..index * 3 + 3)?;
+
+                    rgba.extend_from_slice(color);
+                    rgba.push(*transparency.get(index).unwrap_or(&255));
+                }
+                4 => rgba.extend_from_slice(&[value(0), value(0), value(0), value(1)]),
+                _ => rgba.extend_from_slice(&[value(0), value(1), value(2), value(3)]),
+            }
+        }
+    }
+
+    Some((rgba, width, height))
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let (pa, pb, pc) = (
+        (p - a as i16).abs(),
+        (p - b as i16).abs(),
+        (p - c as i16).abs(),
+    );
+
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+// Inflates a raw deflate stream, giving up on anything that doesn't come out at `size` bytes
+pub fn inflate(data: &[u8], size: usize) -> Option<Vec<u8>> {
+    let mut bits = BitReader {
+        bytes: data,
+        at: 0,
+        bit: 0,
+    };
+    let mut out = Vec::with_capacity(size);
+
+    loop {
+        let last = bits.read(1)? == 1;
+
+        match bits.read(2)? {
+            0 => {
+                bits.align();
+
+                let length = bits.read(16)? as usize;
+                bits.read(16)?;
+
+                let start = bits.at;
+                out.extend_from_slice(data.get(start..start + length)?);
+                bits.at += length;
+            }
+            1 => {
+                let mut lengths = [0u8; 320];
+                lengths[..144].fill(8);
+                lengths[144..256].fill(9);
+                lengths[256..280].fill(7);
+                lengths[280..288].fill(8);
+                lengths[288..].fill(5);
+
+                let literals = Huffman::new(&lengths[..288])?;
+                let distances = Huffman::new(&lengths[288..])?;
+
+                inflate_block(&mut bits, &mut out, &literals, &distances, size)?;
+            }
+            2 => {
+                let literal_count = bits.read(5)? as usize + 257;
+                let distance_count = bits.read(5)? as usize + 1;
+                let code_count = bits.read(4)? as usize + 4;
+
+                const ORDER: [usize; 19] = [
+                    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+                ];
+
+                let mut code_lengths = [0u8; 19];
+
+                for &index in &ORDER[..code_count] {
+                    code_lengths[index] = bits.read(3)? as u8;
+                }
+
+                let codes = Huffman::new(&code_lengths)?;
+                let mut lengths = vec![0u8; literal_count + distance_count];
+                let mut index = 0;
+
+                while index < lengths.len() {
+                    let (value, repeat) = match codes.decode(&mut bits)? {
+                        symbol @ 0..=15 => (symbol as u8, 1),
+                        16 => (*lengths.get(index.checked_sub(1)?)?, 3 + bits.read(2)?),
+                        17 => (0, 3 + bits.read(3)?),
+                        _ => (0, 11 + bits.read(7)?),
+                    };
+
+                    for _ in 0..repeat {
+                        *lengths.get_mut(index)? = value;
+                        index += 1;
+                    }
+                }
+
+                let literals = Huffman::new(&lengths[..literal_count])?;
+                let distances = Huffman::new(&lengths[literal_count..])?;
+
+                inflate_block(&mut bits, &mut out, &literals, &distances, size)?;
+            }
+            _ => return None,
+        }
+
+        if out.len() > size {
+            return None;
+        }
+
+        if last {
+            break;
+        }
+    }
+
+    (out.len() == size).then_some(out)
+}
+
+fn inflate_block(
+    bits: &mut BitReader,
+    out: &mut Vec<u8>,
+    literals: &Huffman,
+    distances: &Huffman,
+    size: usize,
+) -> Option<()> {
+    loop {
+        let symbol = literals.decode(bits)? as usize;
+
+        match symbol {
+            0..=255 => out.push(symbol as u8),
+            256 => return Some(()),
+            _ => {
+                let index = symbol - 257;
+                let length = *LENGTH_BASE.get(index)? as usize
+                    + bits.read(*LENGTH_EXTRA.get(index)? as u32)? as usize;
+
+                let index = distances.decode(bits)? as usize;
+                let distance = *DISTANCE_BASE.get(index)? as usize
+                    + bits.read(*DISTANCE_EXTRA.get(index)? as u32)? as usize;
+
+                let start = out.len().checked_sub(distance)?;
+
+                // Matches can overlap what they're writing, so copy a byte at a time
+                for i in 0..length {
+                    out.push(out[start + i]);
+                }
+            }
+        }
+
+        if out.len() > size {
+            return None;
+        }
+    }
+}
+
+struct BitReader<'a> {
+    bytes: &'a [u8],
+    at: usize,
+    bit: u32,
+}
+
+impl BitReader<'_> {
+    // Values are packed least significant bit first
+    fn read(&mut self, count: u32) -> Option<u32> {
+        let mut value = 0;
+
+        for i in 0..count {
+            let byte = *self.bytes.get(self.at)?;
+            value |= ((byte >> self.bit) as u32 & 1) << i;
+
+            self.bit += 1;
+
+            if self.bit == 8 {
+                self.bit = 0;
+                self.at += 1;
+            }
+        }
+
+        Some(value)
+    }
+
+    fn align(&mut self) {
+        if self.bit > 0 {
+            self.bit = 0;
+            self.at += 1;
+        }
+    }
+}
+
+// Canonical Huffman codes, decoded a bit at a time by counting how many codes there are of each
+// length, the same way zlib's puff does
+struct Huffman {
+    counts: [u16; 16],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Option<Self> {
+        let mut counts = [0u16; 16];
+
+        for &length in lengths {
+            *counts.get_mut(length as usize)? += 1;
+        }
+
+        counts[0] = 0;
+
+        let mut offsets = [0u16; 16];
+
+        for length in 1..15 {
+            offsets[length + 1] = offsets[length] + counts[length];
+        }
+
+        let mut symbols = vec![0u16; lengths.len()];
+
+        for (symbol, &length) in lengths.iter().enumerate() {
+            if length > 0 {
+                symbols[offsets[length as usize] as usize] = symbol as u16;
+                offsets[length as usize] += 1;
+            }
+        }
+
+        Some(Self { counts, symbols })
+    }
+
+    fn decode(&self, bits: &mut BitReader) -> Option<u16> {
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+
+        for length in 1..16 {
+            code |= bits.read(1)? as i32;
+
+            let count = self.counts[length] as i32;
+
+            if code - count < first {
+                return self.symbols.get((index + code - first) as usize).copied();
+            }
+
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+
+        None
+    }
+}
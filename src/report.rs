@@ -0,0 +1,55 @@
+// Support for the error types from anyhow and eyre, enabled by the features of the same name
+
+use crate::trace::{self, RayFrame};
+use crate::Ray;
+
+// anyhow only captures a backtrace when RUST_BACKTRACE or RUST_LIB_BACKTRACE is set, otherwise
+// the best we can do is where the error was sent from
+#[cfg(feature = "anyhow")]
+pub fn anyhow_frames(error: &anyhow::Error) -> Vec<RayFrame> {
+    let backtrace = error.backtrace();
+
+    match backtrace.status() {
+        std::backtrace::BacktraceStatus::Captured => trace::user_frames(&backtrace.to_string()),
+        _ => trace::capture(),
+    }
+}
+
+// eyre hands the backtrace to its report handler, which doesn't expose it, so look for it in the
+// Debug output the handler writes
+#[cfg(feature = "eyre")]
+pub fn eyre_frames(report: &eyre::Report) -> Vec<RayFrame> {
+    let debug = format!("{:?}", report);
+
+    match debug.split_once("Stack backtrace:") {
+        Some((_, backtrace)) => trace::user_frames(backtrace),
+        None => trace::capture(),
+    }
+}
+
+// Sends the error to Ray when a `Result` is an `Err`, and hands the `Result` back untouched
+pub trait RayResultExt {
+    fn ray_err(self) -> Self;
+}
+
+#[cfg(feature = "anyhow")]
+impl<T> RayResultExt for Result<T, anyhow::Error> {
+    fn ray_err(self) -> Self {
+        if let Err(error) = &self {
+            Ray::new().anyhow(error);
+        }
+
+        self
+    }
+}
+
+#[cfg(feature = "eyre")]
+impl<T> RayResultExt for Result<T, eyre::Report> {
+    fn ray_err(self) -> Self {
+        if let Err(report) = &self {
+            Ray::new().eyre(report);
+        }
+
+        self
+    }
+}
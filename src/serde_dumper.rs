@@ -0,0 +1,424 @@
+// A serde::Serializer that walks any Serialize value and builds the same node tree the Debug
+// dumper uses, so both end up with the same HTML. Going through serde means we keep the real
+// types of the leaves (null, bool, number, string) instead of guessing them from Debug text.
+
+use crate::dumper::{node_to_html, pre_to_html, DebugNode, DumpOptions};
+use serde::ser::{self, Serialize};
+
+#[derive(Debug)]
+pub struct SerdeDumpError(String);
+
+impl std::fmt::Display for SerdeDumpError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for SerdeDumpError {}
+
+impl ser::Error for SerdeDumpError {
+    fn custom<T: std::fmt::Display>(msg: T) -> Self {
+        SerdeDumpError(msg.to_string())
+    }
+}
+
+// Renders any `Serialize` value as a collapsible HTML tree
+pub fn serde_to_html<T: Serialize + ?Sized>(value: &T, options: DumpOptions) -> String {
+    match to_node(value) {
+        Ok(node) => node_to_html(&node, options),
+        Err(error) => pre_to_html(&format!("Failed to serialize value: {}", error)),
+    }
+}
+
+// Serializes a value into a dump tree
+pub fn to_node<T: Serialize + ?Sized>(value: &T) -> Result<DebugNode, SerdeDumpError> {
+    value.serialize(NodeSerializer)
+}
+
+fn variant_name(name: &str, variant: &str) -> String {
+    format!("{}::{}", name, variant)
+}
+
+pub struct NodeSerializer;
+
+impl ser::Serializer for NodeSerializer {
+    type Ok = DebugNode;
+    type Error = SerdeDumpError;
+
+    type SerializeSeq = SeqSerializer;
+    type SerializeTuple = SeqSerializer;
+    type SerializeTupleStruct = SeqSerializer;
+    type SerializeTupleVariant = SeqSerializer;
+    type SerializeMap = MapSerializer;
+    type SerializeStruct = StructSerializer;
+    type SerializeStructVariant = StructSerializer;
+
+    fn serialize_bool(self, v: bool) -> Result<DebugNode, SerdeDumpError> {
+        Ok(DebugNode::Bool(v.to_string()))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<DebugNode, SerdeDumpError> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<DebugNode, SerdeDumpError> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<DebugNode, SerdeDumpError> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<DebugNode, SerdeDumpError> {
+        Ok(DebugNode::Number(v.to_string()))
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<DebugNode, SerdeDumpError> {
+        Ok(DebugNode::Number(v.to_string()))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<DebugNode, SerdeDumpError> {
+        self.serialize_u64(v as u64)
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<DebugNode, SerdeDumpError> {
+        self.serialize_u64(v as u64)
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<DebugNode, SerdeDumpError> {
+        self.serialize_u64(v as u64)
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<DebugNode, SerdeDumpError> {
+        Ok(DebugNode::Number(v.to_string()))
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<DebugNode, SerdeDumpError> {
+        Ok(DebugNode::Number(v.to_string()))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<DebugNode, SerdeDumpError> {
+        Ok(DebugNode::Number(format!("{:?}", v)))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<DebugNode, SerdeDumpError> {
+        Ok(DebugNode::Number(format!("{:?}", v)))
+    }
+
+    fn serialize_char(self, v: char) -> Result<DebugNode, SerdeDumpError> {
+        Ok(DebugNode::Char(format!("{:?}", v)))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<DebugNode, SerdeDumpError> {
+        Ok(DebugNode::Str(format!("{:?}", v)))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<DebugNode, SerdeDumpError> {
+        Ok(DebugNode::Seq(
+            v.iter()
+                .map(|byte| DebugNode::Number(byte.to_string()))
+                .collect(),
+        ))
+    }
+
+    fn serialize_none(self) -> Result<DebugNode, SerdeDumpError> {
+        Ok(DebugNode::Null)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<DebugNode, SerdeDumpError> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<DebugNode, SerdeDumpError> {
+        Ok(DebugNode::Null)
+    }
+
+    fn serialize_unit_struct(self, name: &'static str) -> Result<DebugNode, SerdeDumpError> {
+        Ok(DebugNode::Atom(name.to_string()))
+    }
+
+    fn serialize_unit_variant(
+        self,
+        name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<DebugNode, SerdeDumpError> {
+        Ok(DebugNode::Atom(variant_name(name, variant)))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<DebugNode, SerdeDumpError> {
+        Ok(DebugNode::Tuple {
+            name: Some(name.to_string()),
+            items: vec![value.serialize(NodeSerializer)?],
+        })
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<DebugNode, SerdeDumpError> {
+        Ok(DebugNode::Tuple {
+            name: Some(variant_name(name, variant)),
+            items: vec![value.serialize(NodeSerializer)?],
+        })
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SeqSerializer, SerdeDumpError> {
+        Ok(SeqSerializer::new(SeqKind::Seq, len))
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SeqSerializer, SerdeDumpError> {
+        Ok(SeqSerializer::new(SeqKind::Tuple(None), Some(len)))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<SeqSerializer, SerdeDumpError> {
+        Ok(SeqSerializer::new(
+            SeqKind::Tuple(Some(name.to_string())),
+            Some(len),
+        ))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SeqSerializer, SerdeDumpError> {
+        Ok(SeqSerializer::new(
+            SeqKind::Tuple(Some(variant_name(name, variant))),
+            Some(len),
+        ))
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<MapSerializer, SerdeDumpError> {
+        Ok(MapSerializer {
+            entries: Vec::with_capacity(len.unwrap_or(0)),
+            key: None,
+        })
+    }
+
+    fn serialize_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<StructSerializer, SerdeDumpError> {
+        Ok(StructSerializer {
+            name: name.to_string(),
+            fields: Vec::with_capacity(len),
+            non_exhaustive: false,
+        })
+    }
+
+    fn serialize_struct_variant(
+        self,
+        name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<StructSerializer, SerdeDumpError> {
+        Ok(StructSerializer {
+            name: variant_name(name, variant),
+            fields: Vec::with_capacity(len),
+            non_exhaustive: false,
+        })
+    }
+}
+
+pub enum SeqKind {
+    Seq,
+    Tuple(Option<String>),
+}
+
+pub struct SeqSerializer {
+    kind: SeqKind,
+    items: Vec<DebugNode>,
+}
+
+impl SeqSerializer {
+    fn new(kind: SeqKind, len: Option<usize>) -> Self {
+        Self {
+            kind,
+            items: Vec::with_capacity(len.unwrap_or(0)),
+        }
+    }
+
+    fn push<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeDumpError> {
+        self.items.push(value.serialize(NodeSerializer)?);
+
+        Ok(())
+    }
+
+    fn finish(self) -> DebugNode {
+        match self.kind {
+            SeqKind::Seq => DebugNode::Seq(self.items),
+            SeqKind::Tuple(name) => DebugNode::Tuple {
+                name,
+                items: self.items,
+            },
+        }
+    }
+}
+
+impl ser::SerializeSeq for SeqSerializer {
+    type Ok = DebugNode;
+    type Error = SerdeDumpError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<DebugNode, SerdeDumpError> {
+        Ok(self.finish())
+    }
+}
+
+impl ser::SerializeTuple for SeqSerializer {
+    type Ok = DebugNode;
+    type Error = SerdeDumpError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<DebugNode, SerdeDumpError> {
+        Ok(self.finish())
+    }
+}
+
+impl ser::SerializeTupleStruct for SeqSerializer {
+    type Ok = DebugNode;
+    type Error = SerdeDumpError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<DebugNode, SerdeDumpError> {
+        Ok(self.finish())
+    }
+}
+
+impl ser::SerializeTupleVariant for SeqSerializer {
+    type Ok = DebugNode;
+    type Error = SerdeDumpError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<DebugNode, SerdeDumpError> {
+        Ok(self.finish())
+    }
+}
+
+pub struct MapSerializer {
+    entries: Vec<(DebugNode, DebugNode)>,
+    key: Option<DebugNode>,
+}
+
+impl ser::SerializeMap for MapSerializer {
+    type Ok = DebugNode;
+    type Error = SerdeDumpError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Self::Error> {
+        self.key = Some(key.serialize(NodeSerializer)?);
+
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        let key = self
+            .key
+            .take()
+            .ok_or_else(|| SerdeDumpError("serialize_value called before serialize_key".into()))?;
+
+        self.entries.push((key, value.serialize(NodeSerializer)?));
+
+        Ok(())
+    }
+
+    fn end(self) -> Result<DebugNode, SerdeDumpError> {
+        Ok(DebugNode::Map(self.entries))
+    }
+}
+
+pub struct StructSerializer {
+    name: String,
+    fields: Vec<(String, DebugNode)>,
+    non_exhaustive: bool,
+}
+
+impl StructSerializer {
+    fn finish(self) -> DebugNode {
+        DebugNode::Struct {
+            name: self.name,
+            fields: self.fields,
+            non_exhaustive: self.non_exhaustive,
+        }
+    }
+}
+
+impl ser::SerializeStruct for StructSerializer {
+    type Ok = DebugNode;
+    type Error = SerdeDumpError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Self::Error> {
+        self.fields
+            .push((key.to_string(), value.serialize(NodeSerializer)?));
+
+        Ok(())
+    }
+
+    // Fields marked with `skip_serializing_if` still count as part of the struct
+    fn skip_field(&mut self, _key: &'static str) -> Result<(), Self::Error> {
+        self.non_exhaustive = true;
+
+        Ok(())
+    }
+
+    fn end(self) -> Result<DebugNode, SerdeDumpError> {
+        Ok(self.finish())
+    }
+}
+
+impl ser::SerializeStructVariant for StructSerializer {
+    type Ok = DebugNode;
+    type Error = SerdeDumpError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Self::Error> {
+        self.fields
+            .push((key.to_string(), value.serialize(NodeSerializer)?));
+
+        Ok(())
+    }
+
+    fn skip_field(&mut self, _key: &'static str) -> Result<(), Self::Error> {
+        self.non_exhaustive = true;
+
+        Ok(())
+    }
+
+    fn end(self) -> Result<DebugNode, SerdeDumpError> {
+        Ok(self.finish())
+    }
+}
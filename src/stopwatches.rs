@@ -0,0 +1,114 @@
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard, OnceLock};
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone)]
+pub struct RayStopwatch {
+    pub started_at: Instant,
+    pub last_lap_at: Instant,
+    pub memory_at_last_lap: u64,
+    pub max_memory: u64,
+}
+
+// What a stopwatch reports each time it's hit
+#[derive(Debug, Clone)]
+pub struct RayLap {
+    pub is_new_timer: bool,
+    pub total_time: Duration,
+    pub time_since_last_call: Duration,
+    pub memory_usage: u64,
+    pub memory_delta: i64,
+    pub max_memory_usage: u64,
+    pub max_memory_usage_since_last_call: u64,
+}
+
+static STOPWATCHES: OnceLock<Mutex<HashMap<String, RayStopwatch>>> = OnceLock::new();
+
+fn stopwatches() -> MutexGuard<'static, HashMap<String, RayStopwatch>> {
+    STOPWATCHES
+        .get_or_init(|| Mutex::new(HashMap::new()))
+        .lock()
+        .unwrap_or_else(|error| error.into_inner())
+}
+
+// Starts the stopwatch on the first call, every call after that is a lap
+pub fn lap(name: &str) -> RayLap {
+    let now = Instant::now();
+    let memory = memory_usage();
+    let mut stopwatches = stopwatches();
+
+    let Some(stopwatch) = stopwatches.get_mut(name) else {
+        stopwatches.insert(
+            name.to_string(),
+            RayStopwatch {
+                started_at: now,
+                last_lap_at: now,
+                memory_at_last_lap: memory,
+                max_memory: memory,
+            },
+        );
+
+        return RayLap {
+            is_new_timer: true,
+            total_time: Duration::ZERO,
+            time_since_last_call: Duration::ZERO,
+            memory_usage: memory,
+            memory_delta: 0,
+            max_memory_usage: memory,
+            max_memory_usage_since_last_call: memory,
+        };
+    };
+
+    let lap = RayLap {
+        is_new_timer: false,
+        total_time: now - stopwatch.started_at,
+        time_since_last_call: now - stopwatch.last_lap_at,
+        memory_usage: memory,
+        memory_delta: memory as i64 - stopwatch.memory_at_last_lap as i64,
+        max_memory_usage: stopwatch.max_memory.max(memory),
+        max_memory_usage_since_last_call: stopwatch.memory_at_last_lap.max(memory),
+    };
+
+    stopwatch.last_lap_at = now;
+    stopwatch.memory_at_last_lap = memory;
+    stopwatch.max_memory = lap.max_memory_usage;
+
+    lap
+}
+
+// Resident memory of the current process in bytes, or 0 where `/proc` isn't available
+pub fn memory_usage() -> u64 {
+    // statm is measured in pages: size resident shared text lib data dt
+    let Ok(statm) = std::fs::read_to_string("/proc/self/statm") else {
+        return 0;
+    };
+
+    let resident = statm
+        .split_whitespace()
+        .nth(1)
+        .and_then(|pages| pages.parse::<u64>().ok())
+        .unwrap_or(0);
+
+    resident * page_size()
+}
+
+// The kernel hands us the page size in the aux vector as AT_PAGESZ, which saves pulling in libc
+fn page_size() -> u64 {
+    const AT_PAGESZ: usize = 6;
+    const WORD: usize = std::mem::size_of::<usize>();
+
+    static PAGE_SIZE: OnceLock<u64> = OnceLock::new();
+
+    *PAGE_SIZE.get_or_init(|| {
+        let auxv = std::fs::read("/proc/self/auxv").unwrap_or_default();
+
+        auxv.chunks_exact(WORD * 2)
+            .find_map(|pair| {
+                let key = usize::from_ne_bytes(pair[..WORD].try_into().ok()?);
+                let value = usize::from_ne_bytes(pair[WORD..].try_into().ok()?);
+
+                (key == AT_PAGESZ).then_some(value as u64)
+            })
+            .unwrap_or(4096)
+    })
+}
@@ -0,0 +1,125 @@
+// Turns serialized values into the rows of Ray's table payload. Maps and structs become key/value
+// rows, sequences of records become a grid with a header row made of the field names.
+
+use crate::dumper::{escape_html, node_to_html, DebugNode, DumpOptions};
+use crate::message::RayTableValues;
+
+const STYLE_GRID: &str = "display: grid; gap: 0.5em; align-items: start;";
+const STYLE_HEADER: &str = "font-weight: bold;";
+
+pub fn table_values(node: DebugNode) -> RayTableValues {
+    match node {
+        DebugNode::Struct { fields, .. } => RayTableValues(
+            fields
+                .into_iter()
+                .map(|(key, value)| (key, cell(&value)))
+                .collect(),
+        ),
+        DebugNode::Map(entries) => RayTableValues(
+            entries
+                .into_iter()
+                .map(|(key, value)| (key_text(&key), cell(&value)))
+                .collect(),
+        ),
+        DebugNode::Seq(rows) | DebugNode::Tuple { items: rows, .. } | DebugNode::Set(rows) => {
+            grid(rows)
+        }
+        value => RayTableValues(vec![("0".to_string(), cell(&value))]),
+    }
+}
+
+fn grid(rows: Vec<DebugNode>) -> RayTableValues {
+    let rows: Vec<Vec<(String, DebugNode)>> = rows.into_iter().map(record).collect();
+
+    // Columns in the order they first show up, records don't all need the same fields
+    let mut headers: Vec<String> = Vec::new();
+
+    for row in &rows {
+        for (key, _) in row {
+            if !headers.contains(key) {
+                headers.push(key.clone());
+            }
+        }
+    }
+
+    let columns = format!(
+        "{} grid-template-columns: repeat({}, minmax(0, 1fr));",
+        STYLE_GRID,
+        headers.len().max(1)
+    );
+
+    let mut values = Vec::with_capacity(rows.len() + 1);
+
+    let header: String = headers
+        .iter()
+        .map(|header| {
+            format!(
+                "<div style=\"{}\">{}</div>",
+                STYLE_HEADER,
+                escape_html(header)
+            )
+        })
+        .collect();
+
+    values.push((
+        "#".to_string(),
+        format!("<div style=\"{}\">{}</div>", columns, header),
+    ));
+
+    for (index, row) in rows.iter().enumerate() {
+        let cells: String = headers
+            .iter()
+            .map(|header| {
+                let value = row
+                    .iter()
+                    .find(|(key, _)| key == header)
+                    .map(|(_, value)| cell(value))
+                    .unwrap_or_default();
+
+                format!("<div>{}</div>", value)
+            })
+            .collect();
+
+        values.push((
+            index.to_string(),
+            format!("<div style=\"{}\">{}</div>", columns, cells),
+        ));
+    }
+
+    RayTableValues(values)
+}
+
+// Anything that isn't a struct or a map is a record with a single `value` column
+fn record(node: DebugNode) -> Vec<(String, DebugNode)> {
+    match node {
+        DebugNode::Struct { fields, .. } => fields,
+        DebugNode::Map(entries) => entries
+            .into_iter()
+            .map(|(key, value)| (key_text(&key), value))
+            .collect(),
+        value => vec![("value".to_string(), value)],
+    }
+}
+
+fn cell(node: &DebugNode) -> String {
+    node_to_html(
+        node,
+        DumpOptions {
+            expand_depth: 1,
+            max_depth: None,
+        },
+    )
+}
+
+fn key_text(key: &DebugNode) -> String {
+    match key {
+        DebugNode::Str(value) | DebugNode::Char(value) => value
+            .strip_prefix(['"', '\''])
+            .and_then(|value| value.strip_suffix(['"', '\'']))
+            .unwrap_or(value)
+            .to_string(),
+        DebugNode::Number(value) | DebugNode::Bool(value) | DebugNode::Atom(value) => value.clone(),
+        DebugNode::Null => "null".to_string(),
+        key => cell(key),
+    }
+}
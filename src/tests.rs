@@ -27,6 +27,8 @@ mod lib_test {
     #[test]
     #[should_panic(expected = "exited with code 1")]
     fn test_rd_marco_no_args() {
+        Ray::panic_on_die(true);
+
         std::panic::set_hook(Box::new(|panic_info| {
             if let Some(payload) = panic_info.payload().downcast_ref::<&str>() {
                 if *payload == "exited with code 1" {
@@ -42,6 +44,8 @@ mod lib_test {
     #[test]
     #[should_panic(expected = "exited with code 1")]
     fn test_rd_macro_with_one_arg() {
+        Ray::panic_on_die(true);
+
         std::panic::set_hook(Box::new(|panic_info| {
             if let Some(payload) = panic_info.payload().downcast_ref::<&str>() {
                 if *payload == "exited with code 1" {
@@ -57,6 +61,8 @@ mod lib_test {
     #[test]
     #[should_panic(expected = "exited with code 1")]
     fn test_rd_macro_with_multiple_args() {
+        Ray::panic_on_die(true);
+
         std::panic::set_hook(Box::new(|panic_info| {
             if let Some(payload) = panic_info.payload().downcast_ref::<&str>() {
                 if *payload == "exited with code 1" {
@@ -93,6 +99,13 @@ mod lib_test {
         assert_eq!(ray.request.payloads.len(), 1);
     }
 
+    #[test]
+    fn test_ray_dump_serde_function() {
+        let mut ray = Ray::new();
+        ray.dump_serde(&vec![("a", 1), ("b", 2)]);
+        assert_eq!(ray.request.payloads.len(), 1);
+    }
+
     #[test]
     fn test_ray_text_function() {
         let mut ray = Ray::new();
@@ -103,10 +116,67 @@ mod lib_test {
     #[test]
     fn test_ray_color_function() {
         let mut ray = Ray::new();
-        ray.text("Hello, Color").color("green");
+        ray.text("Hello, Color").color(RayColor::Green);
+        assert_eq!(ray.request.payloads.len(), 2);
+    }
+
+    #[test]
+    fn test_ray_color_shortcut_functions() {
+        let mut ray = Ray::new();
+        let uuid = ray.request.uuid.clone();
+
+        ray.text("Hello, Colors")
+            .green()
+            .orange()
+            .red()
+            .purple()
+            .blue()
+            .gray();
+
+        assert_eq!(ray.request.payloads.len(), 7);
+        assert_eq!(ray.request.uuid, uuid);
+
+        match &ray.request.payloads[5].content {
+            RayMessage::Color(payload) => assert_eq!(payload.color, RayColor::Blue),
+            _ => panic!("expected a color payload"),
+        }
+    }
+
+    #[test]
+    fn test_ray_color_from_str() {
+        assert_eq!("Grey".parse::<RayColor>(), Ok(RayColor::Gray));
+        assert!("greem".parse::<RayColor>().is_err());
+        assert_eq!(
+            serde_json::to_string(&RayColor::Purple).unwrap(),
+            "\"purple\""
+        );
+    }
+
+    #[test]
+    fn test_ray_screen_color_function() {
+        let mut ray = Ray::new();
+        ray.screen_color(RayColor::Red);
+        assert_eq!(ray.request.payloads.len(), 1);
+    }
+
+    #[test]
+    fn test_ray_label_function() {
+        let mut ray = Ray::new();
+        ray.text("Hello, Label").label("greeting");
         assert_eq!(ray.request.payloads.len(), 2);
     }
 
+    #[test]
+    fn test_ray_size_functions() {
+        let mut ray = Ray::new();
+        ray.text("Hello, Size").large().small();
+        assert_eq!(ray.request.payloads.len(), 3);
+
+        let payload = serde_json::to_value(&ray.request.payloads[1]).unwrap();
+        assert_eq!(payload["type"], "size");
+        assert_eq!(payload["content"]["size"], "lg");
+    }
+
     #[test]
     fn test_ray_html_function() {
         let mut ray = Ray::new();
@@ -150,187 +220,2750 @@ mod lib_test {
     }
 
     #[test]
-    fn test_ray_disable() {
-        // TODO: Add a test to ensure the request is not sent
+    fn test_ray_count_function() {
         let mut ray = Ray::new();
-        ray.disable();
-        assert_eq!(ray.disabled(), true);
-        assert_eq!(ray.enabled(), false);
+        ray.count(Some("test_ray_count_function"));
+        ray.count(Some("test_ray_count_function"));
+        assert_eq!(ray.request.payloads.len(), 1);
     }
 
     #[test]
-    fn test_ray_enabled() {
-        // TODO: Add a test to ensure the request is sent
+    fn test_ray_counter_value_and_clear_counters() {
+        // Counters are global, so everything that depends on clear_counters lives in one test
+        let mut first = Ray::new();
+        let mut second = Ray::new();
+
+        for ray in [&mut first, &mut second] {
+            ray.count(None);
+        }
+
+        assert_eq!(first.request.uuid, second.request.uuid);
+
+        // A different line is a different call site, so it gets a counter of its own
+        let mut third = Ray::new();
+        third.count(None);
+
+        assert_ne!(first.request.uuid, third.request.uuid);
+
+        match &third.request.payloads[0].content {
+            RayMessage::Custom(custom) => assert_eq!(custom.content, "Called 1 time."),
+            _ => panic!("expected a custom payload"),
+        }
+
         let mut ray = Ray::new();
-        ray.enable();
-        assert_eq!(ray.enabled(), true);
-        assert_eq!(ray.disabled(), false);
+
+        for _ in 0..3 {
+            ray.count(Some("test_ray_counter_value"));
+        }
+
+        assert_eq!(ray.counter_value("test_ray_counter_value"), 3);
+        assert_eq!(ray.counter_value("test_ray_counter_missing"), 0);
+
+        ray.clear_counters();
+        assert_eq!(ray.counter_value("test_ray_counter_value"), 0);
     }
-}
 
-#[cfg(test)]
-#[cfg(feature = "with_tokio")]
-mod async_lib_test {
-    use super::*;
+    #[test]
+    fn test_ray_measure_function() {
+        let mut ray = Ray::new();
+        ray.measure(Some("test_ray_measure_function"));
+        ray.measure(Some("test_ray_measure_function"));
+        assert_eq!(ray.request.payloads.len(), 2);
 
-    #[tokio::test]
-    async fn test_ray_macro_no_args() {
-        let ray = ray!();
-        assert_eq!(ray.request.payloads.len(), 0);
+        match &ray.request.payloads[1].content {
+            RayMessage::Measure(measure) => {
+                assert!(!measure.is_new_timer);
+                assert!(measure.total_time >= measure.time_since_last_call);
+            }
+            _ => panic!("expected a measure payload"),
+        }
     }
 
-    #[tokio::test]
-    async fn test_ray_macro_with_one_arg() {
-        let ray = ray!("Hello, Ray Macro");
+    #[test]
+    fn test_ray_measure_fn_function() {
+        let mut ray = Ray::new();
+        let result = ray.measure_fn(|| 21 * 2);
+        assert_eq!(result, 42);
         assert_eq!(ray.request.payloads.len(), 1);
     }
 
-    #[tokio::test]
-    async fn test_ray_macro_with_multiple_args() {
-        let ray = ray!("Hello", "Ray Macro");
+    #[test]
+    fn test_ray_pause_function() {
+        let mut ray = Ray::new();
+        ray.text("Paused here");
+        ray.pause_with(RayPauseOptions {
+            poll_interval: std::time::Duration::from_millis(10),
+            timeout: Some(std::time::Duration::from_millis(100)),
+            stop_execution: false,
+        });
+
+        // The released lock mustn't be posted again by later calls, the text is still there
         assert_eq!(ray.request.payloads.len(), 1);
+        assert!(matches!(
+            ray.request.payloads[0].content,
+            RayMessage::Text(_)
+        ));
     }
 
-    #[tokio::test]
-    #[should_panic(expected = "exited with code 1")]
-    async fn test_rd_marco_no_args() {
-        std::panic::set_hook(Box::new(|panic_info| {
-            if let Some(payload) = panic_info.payload().downcast_ref::<&str>() {
-                if *payload == "exited with code 1" {
-                    std::process::exit(1);
-                }
-            }
+    #[test]
+    fn test_ray_pause_stop_execution() {
+        Ray::panic_on_die(true);
+
+        let mut ray = Ray::new();
+        ray.disable();
+        ray.text("Paused here");
+        let name = ray.create_lock();
+
+        let status = RayLockStatus {
+            active: true,
+            stop_execution: true,
+        };
+
+        let stopped = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            ray.lock_released(
+                &name,
+                &status,
+                &RayPauseOptions::default(),
+                std::time::Instant::now(),
+            )
         }));
 
-        let ray = rd!();
+        // The lock is gone before die flushes the rest
+        assert!(stopped.is_err());
+        assert_eq!(ray.request.payloads.len(), 1);
+        assert!(matches!(
+            ray.request.payloads[0].content,
+            RayMessage::Text(_)
+        ));
+    }
+
+    #[test]
+    fn test_ray_pause_disabled() {
+        let mut ray = Ray::new();
+        ray.disable().pause();
         assert_eq!(ray.request.payloads.len(), 0);
     }
 
-    #[tokio::test]
-    #[should_panic(expected = "exited with code 1")]
-    async fn test_rd_macro_with_one_arg() {
-        std::panic::set_hook(Box::new(|panic_info| {
-            if let Some(payload) = panic_info.payload().downcast_ref::<&str>() {
-                if *payload == "exited with code 1" {
-                    std::process::exit(1);
-                }
+    #[test]
+    fn test_ray_trace_function() {
+        let mut ray = Ray::new();
+        ray.trace();
+        assert_eq!(ray.request.payloads.len(), 1);
+
+        match &ray.request.payloads[0].content {
+            RayMessage::Trace(trace) => {
+                assert_eq!(trace.frames[0].method, "test_ray_trace_function");
+                assert!(trace.frames[0].file_name.ends_with("src/tests.rs"));
             }
-        }));
+            _ => panic!("expected a trace payload"),
+        }
+    }
 
-        let ray = rd!("Hello, Rd Macro");
-        assert_eq!(ray.request.payloads.len(), 1);
+    #[test]
+    fn test_ray_trace_with_limit() {
+        let mut ray = Ray::new();
+        ray.trace_with(RayTraceOptions {
+            limit: Some(0),
+            offset: 0,
+        });
+
+        match &ray.request.payloads[0].content {
+            RayMessage::Trace(trace) => assert!(trace.frames.is_empty()),
+            _ => panic!("expected a trace payload"),
+        }
     }
 
-    #[tokio::test]
-    #[should_panic(expected = "exited with code 1")]
-    async fn test_rd_macro_with_multiple_args() {
-        std::panic::set_hook(Box::new(|panic_info| {
-            if let Some(payload) = panic_info.payload().downcast_ref::<&str>() {
-                if *payload == "exited with code 1" {
-                    std::process::exit(1);
-                }
-            }
-        }));
+    #[test]
+    fn test_ray_caller_function() {
+        #[inline(never)]
+        fn called_function(ray: &mut Ray) {
+            ray.caller();
+        }
 
-        let ray = rd!("Hello", "Rd Macro");
+        let mut ray = Ray::new();
+        called_function(&mut ray);
         assert_eq!(ray.request.payloads.len(), 1);
+
+        match &ray.request.payloads[0].content {
+            RayMessage::Caller(caller) => {
+                assert_eq!(caller.frame.method, "test_ray_caller_function");
+            }
+            _ => panic!("expected a caller payload"),
+        }
     }
 
-    #[tokio::test]
-    async fn test_ray_macro_with_struct() {
-        #[derive(Debug, Serialize, Deserialize, Clone)]
-        struct TestStruct {
-            name: String,
-            age: i32,
+    #[test]
+    fn test_ray_origin_skipped_when_disabled() {
+        let mut ray = Ray::new();
+        ray.disable();
+        ray.text("Hello, Origin!");
+
+        let origin = &ray.request.payloads[0].origin;
+        assert!(!origin.resolved);
+        assert_eq!(origin.file, "");
+    }
+
+    #[test]
+    fn test_ray_origin() {
+        let mut ray = Ray::new();
+        ray.text("Hello, Origin!");
+
+        let origin = &ray.request.payloads[0].origin;
+        assert!(origin.function_name.contains("test_ray_origin"));
+        assert!(origin.file.ends_with("src/tests.rs"));
+        assert!(origin.line_number > 0);
+    }
+
+    #[test]
+    fn test_ray_error_function_trait_object() {
+        let parsed: Result<u8, Box<dyn std::error::Error>> =
+            "300".parse::<u8>().map_err(Into::into);
+        let error = parsed.unwrap_err();
+
+        let mut ray = Ray::new();
+        ray.error(&*error);
+
+        match &ray.request.payloads[0].content {
+            RayMessage::Exception(exception) => {
+                assert_eq!(
+                    exception.class,
+                    std::any::type_name::<std::num::ParseIntError>()
+                );
+                assert_eq!(exception.message, "number too large to fit in target type");
+            }
+            _ => panic!("expected an exception payload"),
         }
+    }
 
-        let test_struct = TestStruct {
-            name: "John".to_string(),
-            age: 30,
-        };
+    #[test]
+    fn test_ray_error_function_boxed_io_error() {
+        let error: Box<dyn std::error::Error> = std::fs::read("/nope").unwrap_err().into();
 
-        let ray = ray!(test_struct);
-        assert_eq!(ray.request.payloads.len(), 1);
+        let mut ray = Ray::new();
+        ray.error(&*error);
+
+        match &ray.request.payloads[0].content {
+            RayMessage::Exception(exception) => {
+                assert_eq!(exception.class, std::any::type_name::<std::io::Error>());
+            }
+            _ => panic!("expected an exception payload"),
+        }
+
+        // Nothing to downcast to, so there's no type name to show
+        let error: Box<dyn std::error::Error + Send + Sync> = "not an io error".into();
+        ray.error(&*error);
+
+        match &ray.request.payloads[1].content {
+            RayMessage::Exception(exception) => assert_eq!(exception.class, "Error"),
+            _ => panic!("expected an exception payload"),
+        }
     }
 
-    #[tokio::test]
-    async fn test_ray_log_function() {
+    #[test]
+    fn test_ray_error_function() {
+        #[derive(Debug)]
+        struct TestError(std::io::Error);
+
+        impl std::fmt::Display for TestError {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, "failed to load config")
+            }
+        }
+
+        impl std::error::Error for TestError {
+            fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+                Some(&self.0)
+            }
+        }
+
+        let error = TestError(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            "config.toml not found",
+        ));
+
         let mut ray = Ray::new();
-        ray.log(vec!["Hello, Log!".to_string()]);
+        ray.error(&error);
         assert_eq!(ray.request.payloads.len(), 1);
+
+        match &ray.request.payloads[0].content {
+            RayMessage::Exception(exception) => {
+                assert!(exception.class.ends_with("TestError"));
+                assert_eq!(
+                    exception.message,
+                    "failed to load config\n\nCaused by:\n    0: config.toml not found"
+                );
+                assert!(!exception.frames.is_empty());
+            }
+            _ => panic!("expected an exception payload"),
+        }
     }
 
-    #[tokio::test]
-    async fn test_ray_text_function() {
+    #[test]
+    fn test_ray_hide_function() {
         let mut ray = Ray::new();
-        ray.text("Hello, Text!");
-        assert_eq!(ray.request.payloads.len(), 1);
+        ray.text("Hello, Hide").hide();
+        assert_eq!(ray.request.payloads.len(), 2);
     }
 
-    #[tokio::test]
-    async fn test_ray_color_function() {
+    #[test]
+    fn test_ray_remove_function() {
         let mut ray = Ray::new();
-        ray.text("Hello, Color").color("green");
+        ray.text("Hello, Remove").remove();
         assert_eq!(ray.request.payloads.len(), 2);
     }
 
-    #[tokio::test]
-    async fn test_ray_html_function() {
+    #[test]
+    fn test_ray_remove_when_function() {
         let mut ray = Ray::new();
-        ray.html("<strong>Hello, HTML!</strong>");
+        ray.text("Hello, Remove When").remove_when(false);
         assert_eq!(ray.request.payloads.len(), 1);
+
+        ray.remove_when(|| true);
+        assert_eq!(ray.request.payloads.len(), 2);
     }
 
-    #[tokio::test]
-    async fn test_ray_clear_all_function() {
+    #[test]
+    fn test_ray_show_when_function() {
         let mut ray = Ray::new();
-        ray.clear_all();
+        ray.text("Hello, Show When").show_when(true);
         assert_eq!(ray.request.payloads.len(), 1);
+
+        ray.show_when(|| false);
+        assert_eq!(ray.request.payloads.len(), 2);
     }
 
-    #[tokio::test]
-    async fn test_ray_confetti_function() {
+    #[test]
+    fn test_ray_show_app_function() {
         let mut ray = Ray::new();
-        ray.confetti();
-        assert_eq!(ray.request.payloads.len(), 1);
+        ray.text("Hello").show_app().green();
+
+        // Like notify it goes out on its own, so later calls don't post it again
+        assert_eq!(ray.request.payloads.len(), 2);
+        assert!(!ray
+            .request
+            .payloads
+            .iter()
+            .any(|payload| matches!(payload.content, RayMessage::ShowApp(_))));
     }
 
-    #[tokio::test]
-    async fn test_ray_charles_function() {
+    #[test]
+    fn test_ray_hide_app_function() {
         let mut ray = Ray::new();
-        ray.charles();
-        assert_eq!(ray.request.payloads.len(), 1);
+        ray.text("Hello").hide_app().green();
+
+        // Like notify it goes out on its own, so later calls don't post it again
+        assert_eq!(ray.request.payloads.len(), 2);
+        assert!(!ray
+            .request
+            .payloads
+            .iter()
+            .any(|payload| matches!(payload.content, RayMessage::HideApp(_))));
     }
 
-    #[tokio::test]
-    async fn test_ray_new_screen_function() {
+    #[test]
+    fn test_ray_notify_function() {
         let mut ray = Ray::new();
-        ray.new_screen(None);
-        assert_eq!(ray.request.payloads.len(), 1);
+        ray.text("Hello").notify("Hello, Notify!").green();
+
+        // The notification goes out in a request of its own, so it isn't posted again
+        assert_eq!(ray.request.payloads.len(), 2);
+        assert!(!ray
+            .request
+            .payloads
+            .iter()
+            .any(|payload| matches!(payload.content, RayMessage::Notify(_))));
     }
 
-    #[tokio::test]
-    async fn test_ray_new_screen_with_name_function() {
+    #[test]
+    fn test_ray_table_function() {
+        let mut map = std::collections::BTreeMap::new();
+        map.insert("name", "John");
+        map.insert("role", "admin");
+
         let mut ray = Ray::new();
-        ray.new_screen(Some("Hello, New Screen!"));
+        ray.table(&map, None);
         assert_eq!(ray.request.payloads.len(), 1);
+
+        let payload = serde_json::to_value(&ray.request.payloads[0]).unwrap();
+        assert_eq!(payload["type"], "table");
+        assert_eq!(payload["content"]["label"], "Table");
+        assert!(payload["content"]["values"]["role"]
+            .as_str()
+            .unwrap()
+            .contains("admin"));
     }
 
-    #[tokio::test]
-    async fn test_ray_disable() {
-        // TODO: Add a test to ensure the request is not sent
+    #[test]
+    fn test_ray_table_rows_function() {
+        #[derive(Serialize)]
+        struct User {
+            name: &'static str,
+            age: u8,
+        }
+
+        let users = [
+            User {
+                name: "John",
+                age: 30,
+            },
+            User {
+                name: "Jane",
+                age: 28,
+            },
+        ];
+
         let mut ray = Ray::new();
-        ray.disable();
-        assert_eq!(ray.disabled(), true);
-        assert_eq!(ray.enabled(), false);
+        ray.table_rows(users.iter(), Some("Users"));
+
+        match &ray.request.payloads[0].content {
+            RayMessage::Table(table) => {
+                assert_eq!(table.label, "Users");
+
+                let keys: Vec<_> = table.values.0.iter().map(|(key, _)| key.as_str()).collect();
+                assert_eq!(keys, ["#", "0", "1"]);
+                assert!(table.values.0[0].1.contains("name"));
+                assert!(table.values.0[0].1.contains("age"));
+            }
+            _ => panic!("expected a table payload"),
+        }
     }
 
-    #[tokio::test]
-    async fn test_ray_enabled() {
-        // TODO: Add a test to ensure the request is sent
+    #[test]
+    fn test_ray_json_function() {
         let mut ray = Ray::new();
-        ray.enable();
-        assert_eq!(ray.enabled(), true);
-        assert_eq!(ray.disabled(), false);
+        ray.json(r#"{"b": 1, "a": [true, null]}"#);
+        assert_eq!(ray.request.payloads.len(), 1);
+
+        match &ray.request.payloads[0].content {
+            RayMessage::JsonString(json) => assert!(json.value.starts_with("{\n    \"b\": 1")),
+            _ => panic!("expected a json_string payload"),
+        }
+    }
+
+    #[test]
+    fn test_ray_json_function_invalid() {
+        let mut ray = Ray::new();
+        ray.json("{\n  \"a\": 1,\n  \"b\": }");
+
+        match &ray.request.payloads[0].content {
+            RayMessage::Html(html) => assert!(html.content.contains("line 3, column")),
+            _ => panic!("expected an html payload"),
+        }
+    }
+
+    #[test]
+    fn test_ray_to_json_function() {
+        #[derive(Serialize)]
+        struct User {
+            name: &'static str,
+            tags: Vec<&'static str>,
+        }
+
+        let mut ray = Ray::new();
+        ray.to_json(&User {
+            name: "John",
+            tags: vec![],
+        });
+
+        match &ray.request.payloads[0].content {
+            RayMessage::JsonString(json) => {
+                assert_eq!(
+                    json.value,
+                    "{\n    \"name\": \"John\",\n    \"tags\": []\n}"
+                )
+            }
+            _ => panic!("expected a json_string payload"),
+        }
+    }
+
+    #[test]
+    fn test_ray_xml_function() {
+        let mut ray = Ray::new();
+        ray.xml("<feed><title>Hello, XML</title></feed>");
+        assert_eq!(ray.request.payloads.len(), 1);
+
+        let payload = serde_json::to_value(&ray.request.payloads[0]).unwrap();
+        assert_eq!(payload["type"], "custom");
+        assert_eq!(payload["content"]["label"], "XML");
+    }
+
+    #[test]
+    fn test_ray_xml_function_malformed() {
+        let mut ray = Ray::new();
+        ray.xml("<feed>\n  <title>Hello</feed>");
+
+        match &ray.request.payloads[0].content {
+            RayMessage::Html(html) => assert!(html.content.contains("line 2, column 15")),
+            _ => panic!("expected an html payload"),
+        }
+    }
+
+    #[test]
+    fn test_ray_image_function() {
+        let mut ray = Ray::new();
+        ray.image(b"GIF89a\x02\x00\x03\x00".as_slice());
+        ray.image("https://example.com/cat.png");
+        assert_eq!(ray.request.payloads.len(), 2);
+
+        let payload = serde_json::to_value(&ray.request.payloads[0]).unwrap();
+        assert_eq!(payload["type"], "custom");
+        assert_eq!(payload["content"]["label"], "Image");
+
+        match &ray.request.payloads[1].content {
+            RayMessage::Image(image) => {
+                assert!(image
+                    .content
+                    .starts_with("<img src=\"https://example.com/cat.png\""))
+            }
+            _ => panic!("expected an image payload"),
+        }
+    }
+
+    #[test]
+    fn test_ray_image_function_limits() {
+        let mut ray = Ray::new();
+        ray.image_with(
+            vec![0u8; 2048],
+            RayImageOptions {
+                max_bytes: Some(1024),
+                ..RayImageOptions::default()
+            },
+        );
+        ray.image("/does/not/exist.png");
+
+        match &ray.request.payloads[0].content {
+            RayMessage::Text(text) => {
+                assert_eq!(
+                    text.content,
+                    "Image is 2.0 KB, which is over the 1.0 KB limit"
+                )
+            }
+            _ => panic!("expected a text payload"),
+        }
+
+        match &ray.request.payloads[1].content {
+            RayMessage::Text(text) => {
+                assert!(text
+                    .content
+                    .starts_with("Couldn't read image /does/not/exist.png"))
+            }
+            _ => panic!("expected a text payload"),
+        }
+    }
+
+    #[test]
+    fn test_ray_pixels_function() {
+        let mut ray = Ray::new();
+        ray.pixels(&[0u8, 64, 128, 255], 2, 2, RayPixelFormat::Gray);
+        ray.pixels(&[0u8; 5], 2, 2, RayPixelFormat::Gray);
+        assert_eq!(ray.request.payloads.len(), 2);
+
+        match &ray.request.payloads[0].content {
+            RayMessage::Image(image) => assert!(image.content.contains("image/png 2×2")),
+            _ => panic!("expected an image payload"),
+        }
+
+        match &ray.request.payloads[1].content {
+            RayMessage::Text(text) => {
+                assert_eq!(
+                    text.content,
+                    "Expected 4 values for a 2×2 Gray image but got 5"
+                )
+            }
+            _ => panic!("expected a text payload"),
+        }
+    }
+
+    #[test]
+    fn test_ray_file_function() {
+        let mut ray = Ray::new();
+        ray.file_with(
+            "Cargo.toml",
+            RayFileOptions {
+                lines: Some((2, 3)),
+                highlight: Some(3),
+                ..RayFileOptions::default()
+            },
+        );
+        ray.file("does/not/exist.txt");
+        assert_eq!(ray.request.payloads.len(), 2);
+
+        let payload = serde_json::to_value(&ray.request.payloads[0]).unwrap();
+        assert_eq!(payload["type"], "custom");
+        assert_eq!(payload["content"]["label"], "File");
+        assert!(payload["origin"]["file"]
+            .as_str()
+            .unwrap()
+            .ends_with("Cargo.toml"));
+        assert_eq!(payload["origin"]["line_number"], 3);
+        assert!(payload["content"]["content"]
+            .as_str()
+            .unwrap()
+            .contains("name = &quot;ray-rust&quot;"));
+
+        match &ray.request.payloads[1].content {
+            RayMessage::Text(text) => {
+                assert!(text
+                    .content
+                    .starts_with("Couldn't read file does/not/exist.txt"))
+            }
+            _ => panic!("expected a text payload"),
+        }
+    }
+
+    #[test]
+    fn test_ray_link_function() {
+        let mut ray = Ray::new();
+        ray.link("https://spatie.be/docs/ray", Some("Ray docs"));
+        ray.url("example.com/a?b=1&c=2");
+        ray.url("https://exa mple.com");
+        assert_eq!(ray.request.payloads.len(), 3);
+
+        match &ray.request.payloads[0].content {
+            RayMessage::Html(html) => assert_eq!(
+                html.content,
+                "<a href=\"https://spatie.be/docs/ray\">Ray docs</a>"
+            ),
+            _ => panic!("expected an html payload"),
+        }
+
+        match &ray.request.payloads[1].content {
+            RayMessage::Html(html) => assert_eq!(
+                html.content,
+                "<a href=\"https://example.com/a?b=1&amp;c=2\">example.com/a?b=1&amp;c=2</a>"
+            ),
+            _ => panic!("expected an html payload"),
+        }
+
+        match &ray.request.payloads[2].content {
+            RayMessage::Text(text) => {
+                assert_eq!(
+                    text.content,
+                    "URL https://exa mple.com can't contain whitespace"
+                )
+            }
+            _ => panic!("expected a text payload"),
+        }
+    }
+
+    #[test]
+    fn test_ray_separator_function() {
+        let mut ray = Ray::new();
+        ray.separator();
+        assert_eq!(ray.request.payloads.len(), 1);
+
+        let payload = serde_json::to_value(&ray.request.payloads[0]).unwrap();
+        assert_eq!(payload["type"], "separator");
+        assert_eq!(payload["content"], serde_json::json!({}));
+    }
+
+    #[test]
+    fn test_ray_group_function() {
+        let (outer, inner, count) = Ray::group("Request", || {
+            let mut ray = Ray::new();
+            ray.text("inside");
+
+            let outer = match &ray.outgoing().payloads[1].content {
+                RayMessage::Color(color) => color.color,
+                _ => panic!("expected a color payload"),
+            };
+
+            let inner = Ray::group("Query", groups::current);
+
+            // Picking a colour yourself beats the group's
+            ray.red();
+            let count = ray.outgoing().payloads.len();
+
+            (outer, inner, count)
+        });
+
+        assert_ne!(Some(outer), inner);
+        assert_eq!(count, 2);
+        assert_eq!(groups::current(), None);
+        assert_eq!(Ray::new().text("outside").outgoing().payloads.len(), 1);
+    }
+
+    #[test]
+    fn test_ray_info_function() {
+        let mut ray = Ray::new();
+        ray.info();
+        ray.info_with(&["CARGO_PKG_NAME", "RAY_RUST_NOT_SET"]);
+        assert_eq!(ray.request.payloads.len(), 2);
+
+        let payload = serde_json::to_value(&ray.request.payloads[0]).unwrap();
+        assert_eq!(payload["type"], "table");
+        assert_eq!(payload["content"]["label"], "Info");
+        assert_eq!(payload["content"]["values"]["CARGO_PKG_NAME"], "ray-rust");
+        assert_eq!(
+            payload["content"]["values"]["ray-rust"],
+            env!("CARGO_PKG_VERSION")
+        );
+
+        for key in [
+            "rustc",
+            "target",
+            "profile",
+            "features",
+            "os",
+            "cpus",
+            "executable",
+        ] {
+            assert!(
+                payload["content"]["values"][key].is_string(),
+                "missing {}",
+                key
+            );
+        }
+
+        match &ray.request.payloads[1].content {
+            RayMessage::Table(table) => {
+                let keys: Vec<&str> = table.values.0.iter().map(|(key, _)| key.as_str()).collect();
+                assert_eq!(
+                    keys.iter().filter(|key| **key == "CARGO_PKG_NAME").count(),
+                    1
+                );
+                assert!(!keys.contains(&"RAY_RUST_NOT_SET"));
+            }
+            _ => panic!("expected a table payload"),
+        }
+    }
+
+    #[test]
+    fn test_ray_pass_function() {
+        let doubled: Vec<i32> = vec![1, 2, 3]
+            .into_iter()
+            .map(Ray::pass)
+            .map(|value| value * 2)
+            .collect();
+        assert_eq!(doubled, vec![2, 4, 6]);
+
+        let name = Ray::pass(String::from("Ray"));
+        assert_eq!(name, "Ray");
+
+        assert_eq!(ray_pass!(doubled.iter().sum::<i32>()), 12);
+        assert_eq!(ray_pass!(name).len(), 3);
+    }
+
+    #[test]
+    fn test_ray_disable() {
+        // TODO: Add a test to ensure the request is not sent
+        let mut ray = Ray::new();
+        ray.disable();
+        assert!(ray.disabled());
+        assert!(!ray.enabled());
+    }
+
+    #[test]
+    fn test_ray_enabled() {
+        // TODO: Add a test to ensure the request is sent
+        let mut ray = Ray::new();
+        ray.enable();
+        assert!(ray.enabled());
+        assert!(!ray.disabled());
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "with_tokio")]
+mod async_lib_test {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_ray_macro_no_args() {
+        let ray = ray!();
+        assert_eq!(ray.request.payloads.len(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_ray_macro_with_one_arg() {
+        let ray = ray!("Hello, Ray Macro");
+        assert_eq!(ray.request.payloads.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_ray_macro_with_multiple_args() {
+        let ray = ray!("Hello", "Ray Macro");
+        assert_eq!(ray.request.payloads.len(), 1);
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "exited with code 1")]
+    async fn test_rd_marco_no_args() {
+        Ray::panic_on_die(true);
+
+        std::panic::set_hook(Box::new(|panic_info| {
+            if let Some(payload) = panic_info.payload().downcast_ref::<&str>() {
+                if *payload == "exited with code 1" {
+                    std::process::exit(1);
+                }
+            }
+        }));
+
+        let ray = rd!();
+        assert_eq!(ray.request.payloads.len(), 0);
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "exited with code 1")]
+    async fn test_rd_macro_with_one_arg() {
+        Ray::panic_on_die(true);
+
+        std::panic::set_hook(Box::new(|panic_info| {
+            if let Some(payload) = panic_info.payload().downcast_ref::<&str>() {
+                if *payload == "exited with code 1" {
+                    std::process::exit(1);
+                }
+            }
+        }));
+
+        let ray = rd!("Hello, Rd Macro");
+        assert_eq!(ray.request.payloads.len(), 1);
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "exited with code 1")]
+    async fn test_rd_macro_with_multiple_args() {
+        Ray::panic_on_die(true);
+
+        std::panic::set_hook(Box::new(|panic_info| {
+            if let Some(payload) = panic_info.payload().downcast_ref::<&str>() {
+                if *payload == "exited with code 1" {
+                    std::process::exit(1);
+                }
+            }
+        }));
+
+        let ray = rd!("Hello", "Rd Macro");
+        assert_eq!(ray.request.payloads.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_ray_macro_with_struct() {
+        #[derive(Debug, Serialize, Deserialize, Clone)]
+        struct TestStruct {
+            name: String,
+            age: i32,
+        }
+
+        let test_struct = TestStruct {
+            name: "John".to_string(),
+            age: 30,
+        };
+
+        let ray = ray!(test_struct);
+        assert_eq!(ray.request.payloads.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_ray_log_function() {
+        let mut ray = Ray::new();
+        ray.log(vec!["Hello, Log!".to_string()]);
+        assert_eq!(ray.request.payloads.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_ray_dump_serde_function() {
+        let mut ray = Ray::new();
+        ray.dump_serde(&vec![("a", 1), ("b", 2)]);
+        assert_eq!(ray.request.payloads.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_ray_text_function() {
+        let mut ray = Ray::new();
+        ray.text("Hello, Text!");
+        assert_eq!(ray.request.payloads.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_ray_color_function() {
+        let mut ray = Ray::new();
+        ray.text("Hello, Color").color(RayColor::Green);
+        assert_eq!(ray.request.payloads.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_ray_color_shortcut_functions() {
+        let mut ray = Ray::new();
+        let uuid = ray.request.uuid.clone();
+
+        ray.text("Hello, Colors")
+            .green()
+            .orange()
+            .red()
+            .purple()
+            .blue()
+            .gray();
+
+        assert_eq!(ray.request.payloads.len(), 7);
+        assert_eq!(ray.request.uuid, uuid);
+
+        match &ray.request.payloads[5].content {
+            RayMessage::Color(payload) => assert_eq!(payload.color, RayColor::Blue),
+            _ => panic!("expected a color payload"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ray_color_from_str() {
+        assert_eq!("Grey".parse::<RayColor>(), Ok(RayColor::Gray));
+        assert!("greem".parse::<RayColor>().is_err());
+        assert_eq!(
+            serde_json::to_string(&RayColor::Purple).unwrap(),
+            "\"purple\""
+        );
+    }
+
+    #[tokio::test]
+    async fn test_ray_screen_color_function() {
+        let mut ray = Ray::new();
+        ray.screen_color(RayColor::Red);
+        assert_eq!(ray.request.payloads.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_ray_label_function() {
+        let mut ray = Ray::new();
+        ray.text("Hello, Label").label("greeting");
+        assert_eq!(ray.request.payloads.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_ray_size_functions() {
+        let mut ray = Ray::new();
+        ray.text("Hello, Size").large().small();
+        assert_eq!(ray.request.payloads.len(), 3);
+
+        let payload = serde_json::to_value(&ray.request.payloads[1]).unwrap();
+        assert_eq!(payload["type"], "size");
+        assert_eq!(payload["content"]["size"], "lg");
+    }
+
+    #[tokio::test]
+    async fn test_ray_html_function() {
+        let mut ray = Ray::new();
+        ray.html("<strong>Hello, HTML!</strong>");
+        assert_eq!(ray.request.payloads.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_ray_clear_all_function() {
+        let mut ray = Ray::new();
+        ray.clear_all();
+        assert_eq!(ray.request.payloads.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_ray_confetti_function() {
+        let mut ray = Ray::new();
+        ray.confetti();
+        assert_eq!(ray.request.payloads.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_ray_charles_function() {
+        let mut ray = Ray::new();
+        ray.charles();
+        assert_eq!(ray.request.payloads.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_ray_new_screen_function() {
+        let mut ray = Ray::new();
+        ray.new_screen(None);
+        assert_eq!(ray.request.payloads.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_ray_new_screen_with_name_function() {
+        let mut ray = Ray::new();
+        ray.new_screen(Some("Hello, New Screen!"));
+        assert_eq!(ray.request.payloads.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_ray_count_function() {
+        let mut ray = Ray::new();
+        ray.count(Some("test_ray_count_function"));
+        ray.count(Some("test_ray_count_function"));
+        assert_eq!(ray.request.payloads.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_ray_counter_value_and_clear_counters() {
+        // Counters are global, so everything that depends on clear_counters lives in one test
+        let mut first = Ray::new();
+        let mut second = Ray::new();
+
+        for ray in [&mut first, &mut second] {
+            ray.count(None);
+        }
+
+        assert_eq!(first.request.uuid, second.request.uuid);
+
+        // A different line is a different call site, so it gets a counter of its own
+        let mut third = Ray::new();
+        third.count(None);
+
+        assert_ne!(first.request.uuid, third.request.uuid);
+
+        match &third.request.payloads[0].content {
+            RayMessage::Custom(custom) => assert_eq!(custom.content, "Called 1 time."),
+            _ => panic!("expected a custom payload"),
+        }
+
+        let mut ray = Ray::new();
+
+        for _ in 0..3 {
+            ray.count(Some("test_ray_counter_value"));
+        }
+
+        assert_eq!(ray.counter_value("test_ray_counter_value"), 3);
+        assert_eq!(ray.counter_value("test_ray_counter_missing"), 0);
+
+        ray.clear_counters();
+        assert_eq!(ray.counter_value("test_ray_counter_value"), 0);
+    }
+
+    #[tokio::test]
+    async fn test_ray_measure_function() {
+        let mut ray = Ray::new();
+        ray.measure(Some("test_ray_measure_function"));
+        ray.measure(Some("test_ray_measure_function"));
+        assert_eq!(ray.request.payloads.len(), 2);
+
+        match &ray.request.payloads[1].content {
+            RayMessage::Measure(measure) => {
+                assert!(!measure.is_new_timer);
+                assert!(measure.total_time >= measure.time_since_last_call);
+            }
+            _ => panic!("expected a measure payload"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ray_measure_fn_function() {
+        let mut ray = Ray::new();
+        let result = ray.measure_fn(|| 21 * 2);
+        assert_eq!(result, 42);
+        assert_eq!(ray.request.payloads.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_ray_pause_function() {
+        let mut ray = Ray::new();
+        ray.text("Paused here");
+        ray.pause_async_with(RayPauseOptions {
+            poll_interval: std::time::Duration::from_millis(10),
+            timeout: Some(std::time::Duration::from_millis(100)),
+            stop_execution: false,
+        })
+        .await;
+
+        // The released lock mustn't be posted again by later calls, the text is still there
+        assert_eq!(ray.request.payloads.len(), 1);
+        assert!(matches!(
+            ray.request.payloads[0].content,
+            RayMessage::Text(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_ray_pause_stop_execution() {
+        Ray::panic_on_die(true);
+
+        let mut ray = Ray::new();
+        ray.disable();
+        ray.text("Paused here");
+        let name = ray.create_lock();
+
+        let status = RayLockStatus {
+            active: true,
+            stop_execution: true,
+        };
+
+        let stopped = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            ray.lock_released(
+                &name,
+                &status,
+                &RayPauseOptions::default(),
+                std::time::Instant::now(),
+            )
+        }));
+
+        // The lock is gone before die flushes the rest
+        assert!(stopped.is_err());
+        assert_eq!(ray.request.payloads.len(), 1);
+        assert!(matches!(
+            ray.request.payloads[0].content,
+            RayMessage::Text(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_ray_pause_blocking() {
+        // Turning on with_tokio mustn't change what pause is for code that doesn't use it
+        let mut ray = Ray::new();
+        ray.text("Paused here");
+        ray.pause_with(RayPauseOptions {
+            poll_interval: std::time::Duration::from_millis(10),
+            timeout: Some(std::time::Duration::from_millis(100)),
+            stop_execution: false,
+        });
+
+        assert_eq!(ray.request.payloads.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_ray_pause_disabled() {
+        let mut ray = Ray::new();
+        ray.disable().pause_async().await;
+        ray.pause();
+        assert_eq!(ray.request.payloads.len(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_ray_trace_function() {
+        let mut ray = Ray::new();
+        ray.trace();
+        assert_eq!(ray.request.payloads.len(), 1);
+
+        match &ray.request.payloads[0].content {
+            RayMessage::Trace(trace) => {
+                assert_eq!(trace.frames[0].method, "test_ray_trace_function");
+                assert!(trace.frames[0].file_name.ends_with("src/tests.rs"));
+            }
+            _ => panic!("expected a trace payload"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ray_trace_with_limit() {
+        let mut ray = Ray::new();
+        ray.trace_with(RayTraceOptions {
+            limit: Some(0),
+            offset: 0,
+        });
+
+        match &ray.request.payloads[0].content {
+            RayMessage::Trace(trace) => assert!(trace.frames.is_empty()),
+            _ => panic!("expected a trace payload"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ray_caller_function() {
+        #[inline(never)]
+        fn called_function(ray: &mut Ray) {
+            ray.caller();
+        }
+
+        let mut ray = Ray::new();
+        called_function(&mut ray);
+        assert_eq!(ray.request.payloads.len(), 1);
+
+        match &ray.request.payloads[0].content {
+            RayMessage::Caller(caller) => {
+                assert_eq!(caller.frame.method, "test_ray_caller_function");
+            }
+            _ => panic!("expected a caller payload"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ray_origin_skipped_when_disabled() {
+        let mut ray = Ray::new();
+        ray.disable();
+        ray.text("Hello, Origin!");
+
+        let origin = &ray.request.payloads[0].origin;
+        assert!(!origin.resolved);
+        assert_eq!(origin.file, "");
+    }
+
+    #[tokio::test]
+    async fn test_ray_origin() {
+        let mut ray = Ray::new();
+        ray.text("Hello, Origin!");
+
+        let origin = &ray.request.payloads[0].origin;
+        assert!(origin.function_name.contains("test_ray_origin"));
+        assert!(origin.file.ends_with("src/tests.rs"));
+        assert!(origin.line_number > 0);
+    }
+
+    #[tokio::test]
+    async fn test_ray_error_function_trait_object() {
+        let parsed: Result<u8, Box<dyn std::error::Error>> =
+            "300".parse::<u8>().map_err(Into::into);
+        let error = parsed.unwrap_err();
+
+        let mut ray = Ray::new();
+        ray.error(&*error);
+
+        match &ray.request.payloads[0].content {
+            RayMessage::Exception(exception) => {
+                assert_eq!(
+                    exception.class,
+                    std::any::type_name::<std::num::ParseIntError>()
+                );
+                assert_eq!(exception.message, "number too large to fit in target type");
+            }
+            _ => panic!("expected an exception payload"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ray_error_function_boxed_io_error() {
+        let error: Box<dyn std::error::Error> = std::fs::read("/nope").unwrap_err().into();
+
+        let mut ray = Ray::new();
+        ray.error(&*error);
+
+        match &ray.request.payloads[0].content {
+            RayMessage::Exception(exception) => {
+                assert_eq!(exception.class, std::any::type_name::<std::io::Error>());
+            }
+            _ => panic!("expected an exception payload"),
+        }
+
+        // Nothing to downcast to, so there's no type name to show
+        let error: Box<dyn std::error::Error + Send + Sync> = "not an io error".into();
+        ray.error(&*error);
+
+        match &ray.request.payloads[1].content {
+            RayMessage::Exception(exception) => assert_eq!(exception.class, "Error"),
+            _ => panic!("expected an exception payload"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ray_error_function() {
+        #[derive(Debug)]
+        struct TestError(std::io::Error);
+
+        impl std::fmt::Display for TestError {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, "failed to load config")
+            }
+        }
+
+        impl std::error::Error for TestError {
+            fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+                Some(&self.0)
+            }
+        }
+
+        let error = TestError(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            "config.toml not found",
+        ));
+
+        let mut ray = Ray::new();
+        ray.error(&error);
+        assert_eq!(ray.request.payloads.len(), 1);
+
+        match &ray.request.payloads[0].content {
+            RayMessage::Exception(exception) => {
+                assert!(exception.class.ends_with("TestError"));
+                assert_eq!(
+                    exception.message,
+                    "failed to load config\n\nCaused by:\n    0: config.toml not found"
+                );
+                assert!(!exception.frames.is_empty());
+            }
+            _ => panic!("expected an exception payload"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ray_hide_function() {
+        let mut ray = Ray::new();
+        ray.text("Hello, Hide").hide();
+        assert_eq!(ray.request.payloads.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_ray_remove_function() {
+        let mut ray = Ray::new();
+        ray.text("Hello, Remove").remove();
+        assert_eq!(ray.request.payloads.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_ray_remove_when_function() {
+        let mut ray = Ray::new();
+        ray.text("Hello, Remove When").remove_when(false);
+        assert_eq!(ray.request.payloads.len(), 1);
+
+        ray.remove_when(|| true);
+        assert_eq!(ray.request.payloads.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_ray_show_when_function() {
+        let mut ray = Ray::new();
+        ray.text("Hello, Show When").show_when(true);
+        assert_eq!(ray.request.payloads.len(), 1);
+
+        ray.show_when(|| false);
+        assert_eq!(ray.request.payloads.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_ray_show_app_function() {
+        let mut ray = Ray::new();
+        ray.text("Hello").show_app().green();
+
+        // Like notify it goes out on its own, so later calls don't post it again
+        assert_eq!(ray.request.payloads.len(), 2);
+        assert!(!ray
+            .request
+            .payloads
+            .iter()
+            .any(|payload| matches!(payload.content, RayMessage::ShowApp(_))));
+    }
+
+    #[tokio::test]
+    async fn test_ray_hide_app_function() {
+        let mut ray = Ray::new();
+        ray.text("Hello").hide_app().green();
+
+        // Like notify it goes out on its own, so later calls don't post it again
+        assert_eq!(ray.request.payloads.len(), 2);
+        assert!(!ray
+            .request
+            .payloads
+            .iter()
+            .any(|payload| matches!(payload.content, RayMessage::HideApp(_))));
+    }
+
+    #[tokio::test]
+    async fn test_ray_notify_function() {
+        let mut ray = Ray::new();
+        ray.text("Hello").notify("Hello, Notify!").green();
+
+        // The notification goes out in a request of its own, so it isn't posted again
+        assert_eq!(ray.request.payloads.len(), 2);
+        assert!(!ray
+            .request
+            .payloads
+            .iter()
+            .any(|payload| matches!(payload.content, RayMessage::Notify(_))));
+    }
+
+    #[tokio::test]
+    async fn test_ray_table_function() {
+        let mut map = std::collections::BTreeMap::new();
+        map.insert("name", "John");
+        map.insert("role", "admin");
+
+        let mut ray = Ray::new();
+        ray.table(&map, None);
+        assert_eq!(ray.request.payloads.len(), 1);
+
+        let payload = serde_json::to_value(&ray.request.payloads[0]).unwrap();
+        assert_eq!(payload["type"], "table");
+        assert_eq!(payload["content"]["label"], "Table");
+        assert!(payload["content"]["values"]["role"]
+            .as_str()
+            .unwrap()
+            .contains("admin"));
+    }
+
+    #[tokio::test]
+    async fn test_ray_table_rows_function() {
+        #[derive(Serialize)]
+        struct User {
+            name: &'static str,
+            age: u8,
+        }
+
+        let users = [
+            User {
+                name: "John",
+                age: 30,
+            },
+            User {
+                name: "Jane",
+                age: 28,
+            },
+        ];
+
+        let mut ray = Ray::new();
+        ray.table_rows(users.iter(), Some("Users"));
+
+        match &ray.request.payloads[0].content {
+            RayMessage::Table(table) => {
+                assert_eq!(table.label, "Users");
+
+                let keys: Vec<_> = table.values.0.iter().map(|(key, _)| key.as_str()).collect();
+                assert_eq!(keys, ["#", "0", "1"]);
+                assert!(table.values.0[0].1.contains("name"));
+                assert!(table.values.0[0].1.contains("age"));
+            }
+            _ => panic!("expected a table payload"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ray_json_function() {
+        let mut ray = Ray::new();
+        ray.json(r#"{"b": 1, "a": [true, null]}"#);
+        assert_eq!(ray.request.payloads.len(), 1);
+
+        match &ray.request.payloads[0].content {
+            RayMessage::JsonString(json) => assert!(json.value.starts_with("{\n    \"b\": 1")),
+            _ => panic!("expected a json_string payload"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ray_json_function_invalid() {
+        let mut ray = Ray::new();
+        ray.json("{\n  \"a\": 1,\n  \"b\": }");
+
+        match &ray.request.payloads[0].content {
+            RayMessage::Html(html) => assert!(html.content.contains("line 3, column")),
+            _ => panic!("expected an html payload"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ray_to_json_function() {
+        #[derive(Serialize)]
+        struct User {
+            name: &'static str,
+            tags: Vec<&'static str>,
+        }
+
+        let mut ray = Ray::new();
+        ray.to_json(&User {
+            name: "John",
+            tags: vec![],
+        });
+
+        match &ray.request.payloads[0].content {
+            RayMessage::JsonString(json) => {
+                assert_eq!(
+                    json.value,
+                    "{\n    \"name\": \"John\",\n    \"tags\": []\n}"
+                )
+            }
+            _ => panic!("expected a json_string payload"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ray_xml_function() {
+        let mut ray = Ray::new();
+        ray.xml("<feed><title>Hello, XML</title></feed>");
+        assert_eq!(ray.request.payloads.len(), 1);
+
+        let payload = serde_json::to_value(&ray.request.payloads[0]).unwrap();
+        assert_eq!(payload["type"], "custom");
+        assert_eq!(payload["content"]["label"], "XML");
+    }
+
+    #[tokio::test]
+    async fn test_ray_xml_function_malformed() {
+        let mut ray = Ray::new();
+        ray.xml("<feed>\n  <title>Hello</feed>");
+
+        match &ray.request.payloads[0].content {
+            RayMessage::Html(html) => assert!(html.content.contains("line 2, column 15")),
+            _ => panic!("expected an html payload"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ray_image_function() {
+        let mut ray = Ray::new();
+        ray.image(b"GIF89a\x02\x00\x03\x00".as_slice());
+        ray.image("https://example.com/cat.png");
+        assert_eq!(ray.request.payloads.len(), 2);
+
+        let payload = serde_json::to_value(&ray.request.payloads[0]).unwrap();
+        assert_eq!(payload["type"], "custom");
+        assert_eq!(payload["content"]["label"], "Image");
+
+        match &ray.request.payloads[1].content {
+            RayMessage::Image(image) => {
+                assert!(image
+                    .content
+                    .starts_with("<img src=\"https://example.com/cat.png\""))
+            }
+            _ => panic!("expected an image payload"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ray_image_function_limits() {
+        let mut ray = Ray::new();
+        ray.image_with(
+            vec![0u8; 2048],
+            RayImageOptions {
+                max_bytes: Some(1024),
+                ..RayImageOptions::default()
+            },
+        );
+        ray.image("/does/not/exist.png");
+
+        match &ray.request.payloads[0].content {
+            RayMessage::Text(text) => {
+                assert_eq!(
+                    text.content,
+                    "Image is 2.0 KB, which is over the 1.0 KB limit"
+                )
+            }
+            _ => panic!("expected a text payload"),
+        }
+
+        match &ray.request.payloads[1].content {
+            RayMessage::Text(text) => {
+                assert!(text
+                    .content
+                    .starts_with("Couldn't read image /does/not/exist.png"))
+            }
+            _ => panic!("expected a text payload"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ray_pixels_function() {
+        let mut ray = Ray::new();
+        ray.pixels(&[0u8, 64, 128, 255], 2, 2, RayPixelFormat::Gray);
+        ray.pixels(&[0u8; 5], 2, 2, RayPixelFormat::Gray);
+        assert_eq!(ray.request.payloads.len(), 2);
+
+        match &ray.request.payloads[0].content {
+            RayMessage::Image(image) => assert!(image.content.contains("image/png 2×2")),
+            _ => panic!("expected an image payload"),
+        }
+
+        match &ray.request.payloads[1].content {
+            RayMessage::Text(text) => {
+                assert_eq!(
+                    text.content,
+                    "Expected 4 values for a 2×2 Gray image but got 5"
+                )
+            }
+            _ => panic!("expected a text payload"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ray_file_function() {
+        let mut ray = Ray::new();
+        ray.file_with(
+            "Cargo.toml",
+            RayFileOptions {
+                lines: Some((2, 3)),
+                highlight: Some(3),
+                ..RayFileOptions::default()
+            },
+        );
+        ray.file("does/not/exist.txt");
+        assert_eq!(ray.request.payloads.len(), 2);
+
+        let payload = serde_json::to_value(&ray.request.payloads[0]).unwrap();
+        assert_eq!(payload["type"], "custom");
+        assert_eq!(payload["content"]["label"], "File");
+        assert!(payload["origin"]["file"]
+            .as_str()
+            .unwrap()
+            .ends_with("Cargo.toml"));
+        assert_eq!(payload["origin"]["line_number"], 3);
+        assert!(payload["content"]["content"]
+            .as_str()
+            .unwrap()
+            .contains("name = &quot;ray-rust&quot;"));
+
+        match &ray.request.payloads[1].content {
+            RayMessage::Text(text) => {
+                assert!(text
+                    .content
+                    .starts_with("Couldn't read file does/not/exist.txt"))
+            }
+            _ => panic!("expected a text payload"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ray_link_function() {
+        let mut ray = Ray::new();
+        ray.link("https://spatie.be/docs/ray", Some("Ray docs"));
+        ray.url("example.com/a?b=1&c=2");
+        ray.url("https://exa mple.com");
+        assert_eq!(ray.request.payloads.len(), 3);
+
+        match &ray.request.payloads[0].content {
+            RayMessage::Html(html) => assert_eq!(
+                html.content,
+                "<a href=\"https://spatie.be/docs/ray\">Ray docs</a>"
+            ),
+            _ => panic!("expected an html payload"),
+        }
+
+        match &ray.request.payloads[1].content {
+            RayMessage::Html(html) => assert_eq!(
+                html.content,
+                "<a href=\"https://example.com/a?b=1&amp;c=2\">example.com/a?b=1&amp;c=2</a>"
+            ),
+            _ => panic!("expected an html payload"),
+        }
+
+        match &ray.request.payloads[2].content {
+            RayMessage::Text(text) => {
+                assert_eq!(
+                    text.content,
+                    "URL https://exa mple.com can't contain whitespace"
+                )
+            }
+            _ => panic!("expected a text payload"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ray_separator_function() {
+        let mut ray = Ray::new();
+        ray.separator();
+        assert_eq!(ray.request.payloads.len(), 1);
+
+        let payload = serde_json::to_value(&ray.request.payloads[0]).unwrap();
+        assert_eq!(payload["type"], "separator");
+        assert_eq!(payload["content"], serde_json::json!({}));
+    }
+
+    #[tokio::test]
+    async fn test_ray_group_function() {
+        let (outer, inner, count) = Ray::group("Request", || {
+            let mut ray = Ray::new();
+            ray.text("inside");
+
+            let outer = match &ray.outgoing().payloads[1].content {
+                RayMessage::Color(color) => color.color,
+                _ => panic!("expected a color payload"),
+            };
+
+            let inner = Ray::group("Query", groups::current);
+
+            // Picking a colour yourself beats the group's
+            ray.red();
+            let count = ray.outgoing().payloads.len();
+
+            (outer, inner, count)
+        });
+
+        assert_ne!(Some(outer), inner);
+        assert_eq!(count, 2);
+        assert_eq!(groups::current(), None);
+        assert_eq!(Ray::new().text("outside").outgoing().payloads.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_ray_info_function() {
+        let mut ray = Ray::new();
+        ray.info();
+        ray.info_with(&["CARGO_PKG_NAME", "RAY_RUST_NOT_SET"]);
+        assert_eq!(ray.request.payloads.len(), 2);
+
+        let payload = serde_json::to_value(&ray.request.payloads[0]).unwrap();
+        assert_eq!(payload["type"], "table");
+        assert_eq!(payload["content"]["label"], "Info");
+        assert_eq!(payload["content"]["values"]["CARGO_PKG_NAME"], "ray-rust");
+        assert_eq!(
+            payload["content"]["values"]["ray-rust"],
+            env!("CARGO_PKG_VERSION")
+        );
+
+        for key in [
+            "rustc",
+            "target",
+            "profile",
+            "features",
+            "os",
+            "cpus",
+            "executable",
+        ] {
+            assert!(
+                payload["content"]["values"][key].is_string(),
+                "missing {}",
+                key
+            );
+        }
+
+        match &ray.request.payloads[1].content {
+            RayMessage::Table(table) => {
+                let keys: Vec<&str> = table.values.0.iter().map(|(key, _)| key.as_str()).collect();
+                assert_eq!(
+                    keys.iter().filter(|key| **key == "CARGO_PKG_NAME").count(),
+                    1
+                );
+                assert!(!keys.contains(&"RAY_RUST_NOT_SET"));
+            }
+            _ => panic!("expected a table payload"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ray_pass_function() {
+        let doubled: Vec<i32> = vec![1, 2, 3]
+            .into_iter()
+            .map(Ray::pass)
+            .map(|value| value * 2)
+            .collect();
+        assert_eq!(doubled, vec![2, 4, 6]);
+
+        let name = Ray::pass(String::from("Ray"));
+        assert_eq!(name, "Ray");
+
+        assert_eq!(ray_pass!(doubled.iter().sum::<i32>()), 12);
+        assert_eq!(ray_pass!(name).len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_ray_disable() {
+        // TODO: Add a test to ensure the request is not sent
+        let mut ray = Ray::new();
+        ray.disable();
+        assert!(ray.disabled());
+        assert!(!ray.enabled());
+    }
+
+    #[tokio::test]
+    async fn test_ray_enabled() {
+        // TODO: Add a test to ensure the request is sent
+        let mut ray = Ray::new();
+        ray.enable();
+        assert!(ray.enabled());
+        assert!(!ray.disabled());
+    }
+}
+
+#[cfg(test)]
+mod dumper_test {
+    use super::*;
+    use dumper::{parse, DebugNode};
+
+    #[test]
+    fn test_parse_struct() {
+        #[derive(Debug)]
+        #[allow(dead_code)]
+        struct TestStruct {
+            name: String,
+            tags: Vec<&'static str>,
+            parent: Option<Box<TestStruct>>,
+        }
+
+        let test_struct = TestStruct {
+            name: "John".to_string(),
+            tags: vec!["a", "b"],
+            parent: None,
+        };
+
+        let node = parse(&format!("{:#?}", test_struct)).unwrap();
+
+        match node {
+            DebugNode::Struct { name, fields, .. } => {
+                assert_eq!(name, "TestStruct");
+                assert_eq!(fields.len(), 3);
+                assert_eq!(fields[0].1, DebugNode::Str("\"John\"".to_string()));
+                assert!(matches!(&fields[1].1, DebugNode::Seq(items) if items.len() == 2));
+                assert_eq!(fields[2].1, DebugNode::Atom("None".to_string()));
+            }
+            _ => panic!("expected a struct, got {:?}", node),
+        }
+    }
+
+    #[test]
+    fn test_parse_map_set_and_tuples() {
+        let mut map = std::collections::BTreeMap::new();
+        map.insert("key: \"quoted\"", (1, 'x'));
+
+        let node = parse(&format!("{:#?}", map)).unwrap();
+        assert!(matches!(&node, DebugNode::Map(entries) if entries.len() == 1));
+
+        let set: std::collections::BTreeSet<i32> = [1, 2, 3].into();
+        let node = parse(&format!("{:#?}", set)).unwrap();
+        assert!(matches!(&node, DebugNode::Set(items) if items.len() == 3));
+
+        let node = parse(&format!("{:#?}", Some((1.5, -2)))).unwrap();
+        match node {
+            DebugNode::Tuple { name, items } => {
+                assert_eq!(name.as_deref(), Some("Some"));
+                assert!(matches!(&items[0], DebugNode::Tuple { name: None, .. }));
+            }
+            _ => panic!("expected a tuple, got {:?}", node),
+        }
+    }
+
+    #[test]
+    fn test_parse_enum_struct_variant() {
+        #[derive(Debug)]
+        #[allow(dead_code)]
+        enum TestEnum {
+            Variant { id: u8, ok: bool },
+        }
+
+        let node = parse(&format!("{:#?}", TestEnum::Variant { id: 1, ok: true })).unwrap();
+
+        match node {
+            DebugNode::Struct { name, fields, .. } => {
+                assert_eq!(name, "Variant");
+                assert_eq!(fields[0].1, DebugNode::Number("1".to_string()));
+                assert_eq!(fields[1].1, DebugNode::Bool("true".to_string()));
+            }
+            _ => panic!("expected a struct, got {:?}", node),
+        }
+    }
+
+    #[test]
+    fn test_debug_to_html_escapes_values() {
+        let html = debug_to_html(&format!("{:#?}", vec!["<script>"]));
+
+        assert!(html.contains("<details"));
+        assert!(html.contains("&lt;script&gt;"));
+        assert!(!html.contains("<script>"));
+    }
+
+    #[test]
+    fn test_debug_to_html_falls_back_to_pre() {
+        let html = debug_to_html("not { valid");
+
+        assert!(html.starts_with("<pre"));
+        assert!(html.contains("not { valid"));
+    }
+}
+
+#[cfg(test)]
+mod serde_dumper_test {
+    use super::*;
+    use dumper::DebugNode;
+    use serde_dumper::to_node;
+
+    #[derive(Serialize)]
+    struct TestStruct {
+        name: String,
+        age: Option<u8>,
+        scores: Vec<f64>,
+        kind: TestKind,
+    }
+
+    #[derive(Serialize)]
+    enum TestKind {
+        Admin { level: u8 },
+    }
+
+    fn test_struct() -> TestStruct {
+        TestStruct {
+            name: "John".to_string(),
+            age: None,
+            scores: vec![1.0, 2.5],
+            kind: TestKind::Admin { level: 3 },
+        }
+    }
+
+    #[test]
+    fn test_to_node_keeps_leaf_types() {
+        let node = to_node(&test_struct()).unwrap();
+
+        match node {
+            DebugNode::Struct { name, fields, .. } => {
+                assert_eq!(name, "TestStruct");
+                assert_eq!(fields[0].1, DebugNode::Str("\"John\"".to_string()));
+                assert_eq!(fields[1].1, DebugNode::Null);
+                assert_eq!(
+                    fields[2].1,
+                    DebugNode::Seq(vec![
+                        DebugNode::Number("1.0".to_string()),
+                        DebugNode::Number("2.5".to_string()),
+                    ])
+                );
+                assert!(
+                    matches!(&fields[3].1, DebugNode::Struct { name, .. } if name == "TestKind::Admin")
+                );
+            }
+            _ => panic!("expected a struct, got {:?}", node),
+        }
+    }
+
+    #[test]
+    fn test_to_node_map_keys() {
+        let mut map = std::collections::BTreeMap::new();
+        map.insert(1, true);
+
+        let node = to_node(&map).unwrap();
+
+        assert_eq!(
+            node,
+            DebugNode::Map(vec![(
+                DebugNode::Number("1".to_string()),
+                DebugNode::Bool("true".to_string())
+            )])
+        );
+    }
+
+    #[test]
+    fn test_serde_to_html_max_depth() {
+        let options = DumpOptions {
+            expand_depth: 1,
+            max_depth: Some(0),
+        };
+
+        let html = serde_to_html(&test_struct(), options);
+
+        assert!(html.contains("[…] (2)"));
+        assert!(!html.contains("2.5"));
+    }
+}
+
+#[cfg(test)]
+#[cfg(target_os = "linux")]
+mod stopwatches_test {
+    use super::*;
+
+    #[test]
+    fn test_memory_usage_reads_statm() {
+        let memory = stopwatches::memory_usage();
+        assert!(memory > 0);
+        assert_eq!(memory % 1024, 0);
+    }
+}
+
+#[cfg(test)]
+mod trace_test {
+    use super::*;
+
+    const BACKTRACE: &str = "   0: ray_rust::Ray::trace
+             at ./src/lib.rs:10:9
+   1: <app::Worker as app::Job>::run::{{closure}}
+             at /home/me/app/src/worker.rs:42:13
+   2: core::ops::function::FnOnce::call_once
+             at /rustc/abc/library/core/src/ops/function.rs:250:5
+   3: main
+   4: __libc_start_main
+";
+
+    #[test]
+    fn test_parse_backtrace() {
+        let frames = trace::parse(BACKTRACE);
+
+        assert_eq!(frames.len(), 5);
+        assert_eq!(
+            frames[1].function,
+            "<app::Worker as app::Job>::run::{{closure}}"
+        );
+        assert_eq!(frames[1].file, "/home/me/app/src/worker.rs");
+        assert_eq!(frames[1].line_number, 42);
+        assert_eq!(frames[1].column, 13);
+        assert!(frames[0].file.ends_with("/src/lib.rs"));
+        assert!(frames[3].file.is_empty());
+    }
+
+    #[test]
+    fn test_vendor_frames_are_filtered() {
+        let frames: Vec<_> = trace::parse(BACKTRACE)
+            .into_iter()
+            .filter(|frame| !trace::is_vendor_frame(frame))
+            .collect();
+
+        assert_eq!(frames.len(), 1);
+        assert_eq!(
+            frames[0].class_and_method(),
+            ("<app::Worker as app::Job>".to_string(), "run".to_string())
+        );
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "anyhow")]
+#[cfg(not(feature = "with_tokio"))]
+mod anyhow_test {
+    use super::*;
+    use anyhow::Context;
+
+    fn load_config() -> anyhow::Result<String> {
+        std::fs::read_to_string("/does/not/exist.toml").context("failed to load config")
+    }
+
+    #[test]
+    fn test_ray_anyhow_function() {
+        let error = load_config().unwrap_err();
+
+        let mut ray = Ray::new();
+        ray.anyhow(&error);
+        assert_eq!(ray.request.payloads.len(), 1);
+
+        match &ray.request.payloads[0].content {
+            RayMessage::Exception(exception) => {
+                assert_eq!(exception.class, "anyhow::Error");
+                assert!(exception
+                    .message
+                    .starts_with("failed to load config\n\nCaused by:"));
+                assert!(!exception.frames.is_empty());
+            }
+            _ => panic!("expected an exception payload"),
+        }
+    }
+
+    #[test]
+    fn test_ray_err_returns_result() {
+        assert!(load_config().ray_err().is_err());
+        assert_eq!(Ok::<_, anyhow::Error>(1).ray_err().unwrap(), 1);
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "eyre")]
+#[cfg(not(feature = "with_tokio"))]
+mod eyre_test {
+    use super::*;
+    use eyre::WrapErr;
+
+    fn load_config() -> eyre::Result<String> {
+        std::fs::read_to_string("/does/not/exist.toml").wrap_err("failed to load config")
+    }
+
+    #[test]
+    fn test_ray_eyre_function() {
+        let report = load_config().unwrap_err();
+
+        let mut ray = Ray::new();
+        ray.eyre(&report);
+        assert_eq!(ray.request.payloads.len(), 1);
+
+        match &ray.request.payloads[0].content {
+            RayMessage::Exception(exception) => {
+                assert_eq!(exception.class, "eyre::Report");
+                assert!(exception
+                    .message
+                    .starts_with("failed to load config\n\nCaused by:"));
+            }
+            _ => panic!("expected an exception payload"),
+        }
+    }
+
+    #[test]
+    fn test_ray_err_returns_result() {
+        assert!(load_config().ray_err().is_err());
+    }
+}
+
+// Stands in for Ray on its port and keeps the body of every request it got in full, for tests
+// that look at what a child process sent. Big requests are read slowly, so they're still being
+// sent when the child exits. None when the port is taken, like when Ray itself is running
+fn fake_ray() -> Option<&'static std::sync::Mutex<Vec<String>>> {
+    use std::io::{BufRead, BufReader, Read, Write};
+
+    static BODIES: std::sync::OnceLock<Option<std::sync::Mutex<Vec<String>>>> =
+        std::sync::OnceLock::new();
+
+    BODIES
+        .get_or_init(|| {
+            let listener = std::net::TcpListener::bind("127.0.0.1:23517").ok()?;
+
+            std::thread::spawn(move || {
+                for stream in listener.incoming().flatten() {
+                    std::thread::spawn(move || {
+                        let mut reader = BufReader::new(&stream);
+                        let mut length = 0;
+                        let mut line = String::new();
+
+                        while reader.read_line(&mut line).is_ok_and(|read| read > 2) {
+                            let header = line.to_ascii_lowercase();
+
+                            if let Some(value) = header.strip_prefix("content-length:") {
+                                length = value.trim().parse().unwrap_or(0);
+                            }
+
+                            line.clear();
+                        }
+
+                        if length > 1024 * 1024 {
+                            std::thread::sleep(std::time::Duration::from_millis(500));
+                        }
+
+                        let mut body = vec![0; length];
+
+                        if reader.read_exact(&mut body).is_ok() {
+                            if let Some(bodies) = fake_ray() {
+                                bodies
+                                    .lock()
+                                    .unwrap()
+                                    .push(String::from_utf8_lossy(&body).to_string());
+                            }
+                        }
+
+                        let _ = (&stream).write_all(
+                            b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                        );
+                    });
+                }
+            });
+
+            Some(std::sync::Mutex::new(Vec::new()))
+        })
+        .as_ref()
+}
+
+#[cfg(test)]
+mod panic_test {
+    use super::*;
+
+    #[test]
+    fn test_panic_message_str_payload() {
+        let payload: Box<dyn std::any::Any + Send> = Box::new("boom");
+        let message = panic::panic_message(Some("worker"), "src/main.rs:2:5", payload.as_ref());
+
+        assert_eq!(
+            message,
+            "thread 'worker' panicked at src/main.rs:2:5:\nboom"
+        );
+    }
+
+    #[test]
+    fn test_panic_message_string_payload() {
+        let payload: Box<dyn std::any::Any + Send> = Box::new(format!("index {} out of range", 3));
+        let message = panic::panic_message(None, "src/lib.rs:1:1", payload.as_ref());
+
+        assert_eq!(
+            message,
+            "thread '<unnamed>' panicked at src/lib.rs:1:1:\nindex 3 out of range"
+        );
+    }
+
+    const CHILD_ENV: &str = "RAY_RUST_PANIC_CHILD";
+
+    #[test]
+    fn test_install_panic_hook() {
+        let bodies = fake_ray();
+
+        let output = std::process::Command::new(std::env::current_exe().unwrap())
+            .args(["--exact", "tests::panic_test::panic_child", "--nocapture"])
+            .env(CHILD_ENV, "1")
+            .output()
+            .unwrap();
+
+        assert!(!output.status.success());
+        assert!(String::from_utf8_lossy(&output.stderr).contains("Previous hook: boom from worker"));
+
+        // The previous hook aborts, so the panic only got to Ray if it was sent before handing on
+        if let Some(bodies) = bodies {
+            let bodies = bodies.lock().unwrap();
+
+            assert!(bodies
+                .iter()
+                .any(|body| body.contains("thread 'worker' panicked at")
+                    && body.contains("boom from worker")));
+        }
+    }
+
+    // Only does anything when started by test_install_panic_hook. The previous hook aborts like
+    // panic = "abort" would, so nothing is sent after it
+    #[test]
+    fn panic_child() {
+        if std::env::var(CHILD_ENV).is_err() {
+            return;
+        }
+
+        std::panic::set_hook(Box::new(|info| {
+            let message = panic::panic_message(None, "", info.payload());
+
+            eprintln!(
+                "Previous hook: {}",
+                message.lines().last().unwrap_or_default()
+            );
+
+            std::process::abort();
+        }));
+
+        install_panic_hook();
+
+        let _ = std::thread::Builder::new()
+            .name("worker".to_string())
+            .spawn(|| panic!("boom from worker"))
+            .unwrap()
+            .join();
+    }
+}
+
+#[cfg(test)]
+mod die_test {
+    use super::*;
+
+    const CHILD_ENV: &str = "RAY_RUST_DIE_CHILD";
+
+    #[test]
+    fn test_die_exits_process() {
+        let output = std::process::Command::new(std::env::current_exe().unwrap())
+            .args(["--exact", "tests::die_test::die_child", "--nocapture"])
+            .env(CHILD_ENV, "1")
+            .output()
+            .unwrap();
+
+        assert_eq!(output.status.code(), Some(3));
+    }
+
+    // Only does anything when started by test_die_exits_process, the busy thread would keep the
+    // process alive if die only panicked
+    #[test]
+    fn die_child() {
+        if std::env::var(CHILD_ENV).is_err() {
+            return;
+        }
+
+        std::thread::spawn(|| loop {
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        });
+
+        // Queue the payload without sending it, so die is what sends it and this works without a
+        // tokio runtime too
+        let mut ray = Ray::new();
+        ray.disable().text("Hello, Die!");
+        ray.enable().die(3);
+
+        unreachable!("die should have exited the process");
+    }
+
+    #[test]
+    #[cfg(feature = "with_tokio")]
+    fn test_die_waits_for_other_sends() {
+        let Some(bodies) = fake_ray() else {
+            return;
+        };
+
+        let output = std::process::Command::new(std::env::current_exe().unwrap())
+            .args(["--exact", "tests::die_test::die_tokio_child", "--nocapture"])
+            .env(CHILD_ENV, "1")
+            .output()
+            .unwrap();
+
+        assert_eq!(output.status.code(), Some(3));
+
+        let bodies = bodies.lock().unwrap();
+        assert!(bodies.iter().any(|body| body.contains("Sent before die")));
+        assert!(bodies.iter().any(|body| body.contains("Sent by die")));
+    }
+
+    // The big payload is still being sent on tokio's blocking pool when die is called
+    #[tokio::test]
+    #[cfg(feature = "with_tokio")]
+    async fn die_tokio_child() {
+        if std::env::var(CHILD_ENV).is_err() {
+            return;
+        }
+
+        let mut ray = Ray::new();
+        ray.disable().text("Sent by die");
+
+        Ray::new().text(&format!("Sent before die{}", " ".repeat(8 * 1024 * 1024)));
+
+        ray.enable().die(3);
+
+        unreachable!("die should have exited the process");
+    }
+}
+
+#[cfg(test)]
+mod json_test {
+    use super::*;
+
+    #[test]
+    fn test_pretty_keeps_strings_intact() {
+        let pretty = json::pretty(r#"{"a{,:}":"x\"[1, 2]","b":{},"c":[ ]}"#).unwrap();
+
+        assert_eq!(
+            pretty,
+            "{\n    \"a{,:}\": \"x\\\"[1, 2]\",\n    \"b\": {},\n    \"c\": []\n}"
+        );
+    }
+
+    #[test]
+    fn test_pretty_rejects_invalid_json() {
+        let error = json::pretty("[1, 2").unwrap_err();
+
+        assert_eq!(error.line(), 1);
+        assert_eq!(error.column(), 5);
+    }
+}
+
+#[cfg(test)]
+mod xml_test {
+    use super::*;
+
+    const ENVELOPE: &str = r#"<?xml version="1.0"?><soap:Envelope xmlns:soap="http://www.w3.org/2003/05/soap-envelope"><soap:Body><m:Price a='1'>34.5</m:Price><empty/><!-- note --><data><![CDATA[<raw>]]></data></soap:Body></soap:Envelope>"#;
+
+    #[test]
+    fn test_pretty_html_indents_elements() {
+        let html = xml::pretty_html(ENVELOPE).unwrap();
+
+        assert!(html.starts_with("<pre>"));
+        assert!(html.contains("\n  <span style=\"color: #7c3aed;\">&lt;soap:Body</span>"));
+        assert!(html.contains("34.5<span style=\"color: #7c3aed;\">&lt;/m:Price&gt;</span>"));
+        assert!(html.contains("&lt;![CDATA[&lt;raw&gt;]]&gt;"));
+    }
+
+    #[test]
+    fn test_pretty_html_reports_errors() {
+        let error = xml::pretty_html("<a>\n<b></a>").unwrap_err();
+        assert_eq!((error.line, error.column), (2, 4));
+        assert_eq!(error.message, "expected </b> but found </a>");
+
+        let error = xml::pretty_html("<a b=1></a>").unwrap_err();
+        assert_eq!(error.message, "attribute b needs a quoted value");
+
+        let error = xml::pretty_html("<a><b>").unwrap_err();
+        assert_eq!(error.message, "<b> is never closed");
+
+        let error = xml::pretty_html("<a/><b/>").unwrap_err();
+        assert_eq!(error.message, "more than one root element");
+    }
+}
+
+#[cfg(test)]
+mod image_test {
+    use super::*;
+
+    #[test]
+    fn test_sniff_formats() {
+        let mut png = b"\x89PNG\r\n\x1a\n\x00\x00\x00\x0dIHDR".to_vec();
+        png.extend_from_slice(&[0, 0, 1, 0, 0, 0, 0, 200]);
+        let info = image::sniff(&png).unwrap();
+        assert_eq!(
+            (info.mime, info.width, info.height),
+            ("image/png", 256, 200)
+        );
+
+        let gif = b"GIF87a\x0a\x00\x05\x00";
+        let info = image::sniff(gif).unwrap();
+        assert_eq!((info.mime, info.width, info.height), ("image/gif", 10, 5));
+
+        // SOI, an APP0 segment with no data, then SOF0 with a height of 48 and a width of 64
+        let jpeg = b"\xff\xd8\xff\xe0\x00\x02\xff\xc0\x00\x11\x08\x00\x30\x00\x40";
+        let info = image::sniff(jpeg).unwrap();
+        assert_eq!((info.mime, info.width, info.height), ("image/jpeg", 64, 48));
+
+        let mut webp = b"RIFF\x00\x00\x00\x00WEBPVP8X\x0a\x00\x00\x00\x00\x00\x00\x00".to_vec();
+        webp.extend_from_slice(&[99, 0, 0, 49, 0, 0]);
+        let info = image::sniff(&webp).unwrap();
+        assert_eq!(
+            (info.mime, info.width, info.height),
+            ("image/webp", 100, 50)
+        );
+
+        assert_eq!(image::sniff(b"not an image"), None);
+    }
+
+    #[test]
+    fn test_base64_round_trip() {
+        for value in ["", "f", "fo", "foo", "foob", "fooba", "foobar"] {
+            let encoded = image::encode_base64(value.as_bytes());
+
+            if !value.is_empty() {
+                assert_eq!(image::decode_base64(&encoded).unwrap(), value.as_bytes());
+            }
+        }
+
+        assert_eq!(image::encode_base64(b"foobar"), "Zm9vYmFy");
+        assert_eq!(image::encode_base64(b"fooba"), "Zm9vYmE=");
+        assert_eq!(image::decode_base64("Zm9v\nYmE="), Some(b"fooba".to_vec()));
+        assert_eq!(image::decode_base64("Zm9=YmE="), None);
+        assert_eq!(image::decode_base64("Zm9vY"), None);
+    }
+
+    #[test]
+    fn test_image_sources() {
+        let gif = image::encode_base64(b"GIF89a\x02\x00\x03\x00");
+
+        assert_eq!(
+            RayImage::from("https://example.com/a.png"),
+            RayImage::Url("https://example.com/a.png".to_string())
+        );
+        assert_eq!(RayImage::from(gif.as_str()), RayImage::Base64(gif.clone()));
+        assert_eq!(
+            RayImage::from("images/missing.png"),
+            RayImage::Path("images/missing.png".into())
+        );
+
+        let html = image::image_html(
+            &RayImage::from(format!("data:image/gif;base64,{}", gif)),
+            &RayImageOptions {
+                max_width: Some(100),
+                ..RayImageOptions::default()
+            },
+        )
+        .unwrap();
+
+        assert!(html.contains(&format!("src=\"data:image/gif;base64,{}\"", gif)));
+        assert!(html.contains("style=\"max-width: 100px;\""));
+        assert!(html.contains("image/gif 2×3, 10 B"));
+    }
+
+    #[test]
+    fn test_png_is_scaled_down() {
+        // Two columns of black and white pixels, halving the width averages them to gray
+        let pixels: Vec<u8> = (0..4 * 2)
+            .map(|i| if i % 2 == 0 { 0 } else { 255 })
+            .collect();
+        let png = png::encode(&pixels, 4, 2, 0, 1);
+
+        let html = image::image_html(
+            &RayImage::Bytes(png),
+            &RayImageOptions {
+                max_width: Some(2),
+                ..RayImageOptions::default()
+            },
+        )
+        .unwrap();
+
+        assert!(html.contains("image/png 2×1 (scaled down from 4×2)"));
+
+        let data = html
+            .split("base64,")
+            .nth(1)
+            .unwrap()
+            .split('"')
+            .next()
+            .unwrap();
+        let (rgba, width, height) = png::decode(&image::decode_base64(data).unwrap()).unwrap();
+
+        assert_eq!((width, height), (2, 1));
+        assert_eq!(rgba, vec![128, 128, 128, 255, 128, 128, 128, 255]);
+    }
+
+    #[test]
+    fn test_png_is_scaled_to_max_bytes() {
+        // Noise doesn't compress, so only fewer pixels get it under the limit
+        let mut seed = 1u32;
+        let pixels: Vec<u8> = (0..64 * 64 * 3)
+            .map(|_| {
+                seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+                (seed >> 16) as u8
+            })
+            .collect();
+        let png = png::encode(&pixels, 64, 64, 2, 3);
+
+        let options = RayImageOptions {
+            max_bytes: Some(4096),
+            ..RayImageOptions::default()
+        };
+
+        let html = image::image_html(&RayImage::Bytes(png.clone()), &options).unwrap();
+        assert!(html.contains("(scaled down from 64×64)"));
+
+        // Only PNGs can be scaled, anything else over the limit is still refused
+        let jpeg = [
+            b"\xff\xd8\xff\xe0\x00\x02\xff\xc0\x00\x11\x08\x00\x30\x00\x40".as_slice(),
+            &png,
+        ]
+        .concat();
+        assert!(image::image_html(&RayImage::Bytes(jpeg), &options)
+            .unwrap_err()
+            .contains("over the 4.0 KB limit"));
+    }
+}
+
+#[cfg(test)]
+mod pixels_test {
+    use super::*;
+
+    #[test]
+    fn test_checksums() {
+        assert_eq!(png::crc32(b"123456789"), 0xcbf43926);
+        assert_eq!(png::adler32(b"Wikipedia"), 0x11e60398);
+    }
+
+    #[test]
+    fn test_zlib_compresses_repeats() {
+        let data = [7u8; 10000];
+        let compressed = png::zlib(&data);
+
+        assert!(compressed.len() < 200);
+        assert_eq!(&compressed[..2], &[0x78, 0x01]);
+        assert_eq!(
+            &compressed[compressed.len() - 4..],
+            &png::adler32(&data).to_be_bytes()
+        );
+    }
+
+    #[test]
+    fn test_inflate() {
+        // zlib's own output, a dynamic Huffman block and a stored one
+        let dynamic = [
+            0x15, 0xc9, 0x31, 0x0a, 0x00, 0x40, 0x08, 0xc4, 0xc0, 0xb7, 0x5a, 0x04, 0xb4, 0x59,
+            0x41, 0xf7, 0xff, 0xdc, 0xd9, 0x85, 0x09, 0x83, 0x51, 0x29, 0x40, 0x44, 0x53, 0xe6,
+            0x04, 0x92, 0xa0, 0x59, 0x4a, 0x57, 0xe7, 0x9d, 0xfc, 0xe3, 0xed, 0xc5, 0x1e, 0x6d,
+            0x3c, 0xf0, 0xe5,
+        ];
+        let text = "ereteninaeeneaoeitereteeeheaeoeseinheaeeiteoheetetsosettrnsa";
+        assert_eq!(png::inflate(&dynamic, text.len()).unwrap(), text.as_bytes());
+
+        let stored = b"\x01\x16\x00\xe9\xffstored, not compressed";
+        assert_eq!(png::inflate(stored, 22).unwrap(), b"stored, not compressed");
+
+        // Anything that doesn't come out at the expected size is refused
+        assert_eq!(png::inflate(stored, 21), None);
+        assert_eq!(png::inflate(&dynamic[..20], text.len()), None);
+    }
+
+    #[test]
+    fn test_png_decode() {
+        let pixels: Vec<u8> = (0..3 * 2 * 3).map(|i| i * 10).collect();
+        let png = RayPixels::new(&pixels, 3, 2, RayPixelFormat::Rgb)
+            .to_png()
+            .unwrap();
+        let (rgba, width, height) = png::decode(&png).unwrap();
+
+        assert_eq!((width, height), (3, 2));
+        assert_eq!(&rgba[..8], &[0, 10, 20, 255, 30, 40, 50, 255]);
+
+        // A 2 bit palette image with transparency, its second row uses the up filter
+        let chunk = |kind: &[u8], data: &[u8]| {
+            let mut chunk = (data.len() as u32).to_be_bytes().to_vec();
+            chunk.extend_from_slice(kind);
+            chunk.extend_from_slice(data);
+            chunk.extend_from_slice(&png::crc32(&chunk[4..]).to_be_bytes());
+            chunk
+        };
+
+        let png = [
+            b"\x89PNG\r\n\x1a\n".to_vec(),
+            chunk(b"IHDR", &[0, 0, 0, 4, 0, 0, 0, 2, 2, 3, 0, 0, 0]),
+            chunk(b"PLTE", &[255, 0, 0, 0, 255, 0, 0, 0, 255, 9, 9, 9]),
+            chunk(b"tRNS", &[128]),
+            chunk(b"IDAT", &png::zlib(&[0, 0b00_01_10_11, 2, 0])),
+            chunk(b"IEND", &[]),
+        ]
+        .concat();
+
+        let (rgba, width, height) = png::decode(&png).unwrap();
+        let first_row = [255, 0, 0, 128, 0, 255, 0, 255, 0, 0, 255, 255, 9, 9, 9, 255];
+
+        assert_eq!((width, height), (4, 2));
+        assert_eq!(&rgba[..16], &first_row);
+        assert_eq!(&rgba[16..], &first_row);
+
+        assert_eq!(png::decode(b"\x89PNG\r\n\x1a\n"), None);
+    }
+
+    #[test]
+    fn test_float_buffers_are_stretched() {
+        let pixels = RayPixels::new(&[-1.0f32, 0.0, 1.0, f32::NAN], 2, 2, RayPixelFormat::Gray);
+        assert_eq!(pixels.pixels, vec![0, 128, 255, 0]);
+        assert_eq!(pixels.range, Some((-1.0, 1.0)));
+
+        // Alpha isn't stretched with the colours
+        let pixels = RayPixels::new(&[10.0f32, 0.5, 20.0, 2.0], 2, 1, RayPixelFormat::GrayAlpha);
+        assert_eq!(pixels.pixels, vec![0, 128, 255, 255]);
+    }
+
+    #[test]
+    fn test_png_header() {
+        let png = RayPixels::new(&[255u8; 3 * 4 * 3], 4, 3, RayPixelFormat::Rgb)
+            .to_png()
+            .unwrap();
+
+        let info = image::sniff(&png).unwrap();
+        assert_eq!((info.mime, info.width, info.height), ("image/png", 4, 3));
+        assert!(png.ends_with(b"IEND\xae\x42\x60\x82"));
+    }
+
+    #[test]
+    fn test_compare_pixels_html() {
+        let html = pixels::pixels_html(&[
+            RayPixels::new(&[0u8, 255], 2, 1, RayPixelFormat::Gray),
+            RayPixels::new(&[0.0f32, 0.25], 2, 1, RayPixelFormat::Gray),
+        ])
+        .unwrap();
+
+        assert_eq!(html.matches("<img src=\"data:image/png;base64,").count(), 2);
+        assert!(html.contains("stretched from 0 to 0.25"));
+    }
+}
+
+#[cfg(test)]
+mod file_test {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn temp_file(name: &str, contents: &[u8]) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("ray-rust-{}-{}", std::process::id(), name));
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_file_html_lines() {
+        let path = temp_file("lines.txt", b"one\ntwo <b>\r\nthree\nfour\n");
+
+        let html = file::file_html(
+            &path,
+            &RayFileOptions {
+                lines: Some((2, 3)),
+                highlight: Some(3),
+                ..RayFileOptions::default()
+            },
+        )
+        .unwrap();
+
+        assert!(!html.contains("  1</span>  one"));
+        assert!(html.contains("  2</span>  two &lt;b&gt;</span>"));
+        assert!(html.contains("background-color: #fef3c7;\"><span style=\"color: #9ca3af; user-select: none;\">  3</span>  three"));
+        assert!(!html.contains("  4</span>  four"));
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_file_html_truncates() {
+        let contents: String = (1..=50).map(|n| format!("line {}\n", n)).collect();
+        let path = temp_file("long.txt", contents.as_bytes());
+
+        let html = file::file_html(
+            &path,
+            &RayFileOptions {
+                max_lines: 10,
+                ..RayFileOptions::default()
+            },
+        )
+        .unwrap();
+
+        assert!(html.contains("line 10"));
+        assert!(!html.contains("line 11"));
+        assert!(html.contains("… truncated after 10 lines"));
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_file_html_long_lines() {
+        // One enormous line, like minified JSON, only has its start shown
+        let mut contents = "é".repeat(50_000).into_bytes();
+        contents.extend_from_slice(b"\r\nend\r\n");
+        let path = temp_file("minified.json", &contents);
+
+        let html = file::file_html(
+            &path,
+            &RayFileOptions {
+                max_line_bytes: 5,
+                ..RayFileOptions::default()
+            },
+        )
+        .unwrap();
+
+        assert!(html.contains("  1</span>  éé<span"));
+        assert!(html.contains("… 99996 more bytes"));
+        assert!(html.contains("  2</span>  end</span>"));
+        assert!(html.len() < 1000);
+
+        std::fs::remove_file(path).unwrap();
+
+        // Lots of long lines stop once enough text has been shown
+        let contents = format!("{}\n", "x".repeat(100)).repeat(50);
+        let path = temp_file("wide.txt", contents.as_bytes());
+
+        let html = file::file_html(
+            &path,
+            &RayFileOptions {
+                max_text_bytes: 250,
+                ..RayFileOptions::default()
+            },
+        )
+        .unwrap();
+
+        assert!(html.contains("… truncated after 3 lines"));
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_file_html_binary() {
+        let mut contents = b"\x89PNG\r\n\x1a\n\x00\x00\x00\x0dIHDR".to_vec();
+        contents.extend_from_slice(&[0xff; 32]);
+        let path = temp_file("binary.png", &contents);
+
+        let html = file::file_html(
+            &path,
+            &RayFileOptions {
+                max_bytes: 32,
+                ..RayFileOptions::default()
+            },
+        )
+        .unwrap();
+
+        assert!(html.contains(
+            "00000000</span>  89 50 4e 47 0d 0a 1a 0a 00 00 00 0d 49 48 44 52  |.PNG........IHDR|"
+        ));
+        assert!(html.contains("00000010</span>  ff ff"));
+        assert!(html.contains("… 16 more bytes"));
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
+
+#[cfg(test)]
+mod link_test {
+    use super::*;
+
+    #[test]
+    fn test_href_validation() {
+        assert_eq!(
+            link::href("http://localhost:8080/report").unwrap(),
+            "http://localhost:8080/report"
+        );
+        assert_eq!(
+            link::href("https://[::1]:23517/").unwrap(),
+            "https://[::1]:23517/"
+        );
+        assert_eq!(
+            link::href("vscode://file/src/lib.rs").unwrap(),
+            "vscode://file/src/lib.rs"
+        );
+
+        assert!(link::href("").is_err());
+        assert!(link::href("https://").is_err());
+        assert!(link::href("https://-bad-.com").is_err());
+        assert!(link::href("https://example.com:99999").is_err());
+        assert!(link::href("1http://example.com").is_err());
+        assert!(link::href("file://relative/report.html").is_err());
+        assert!(link::href("javascript://%0Aalert(1)").is_err());
+        assert!(link::href("JavaScript://%0Aalert(1)").is_err());
+        assert!(link::href("data://text/html,hello").is_err());
+        assert!(link::href("javascript:alert(1)").is_err());
+    }
+
+    // Path mapping is global, so every assertion that depends on it lives in this one test
+    #[test]
+    fn test_file_links_use_path_mapping() {
+        assert_eq!(
+            link::href("file:///app/target/my report.html").unwrap(),
+            "file:///app/target/my%20report.html"
+        );
+
+        Ray::path_mapping("/app/", "/home/me/project");
+
+        assert_eq!(
+            link::href("file:///app/target/report.html").unwrap(),
+            "file:///home/me/project/target/report.html"
+        );
+        assert_eq!(
+            link::href("file:///application/report.html").unwrap(),
+            "file:///application/report.html"
+        );
+        assert_eq!(paths::map("/app"), "/home/me/project");
+
+        // Frames from trace, caller, errors and panics link to the same place
+        let frame = RayTraceFrame::from(trace::RayFrame {
+            function: "app::main".to_string(),
+            file: "/app/src/main.rs".to_string(),
+            line_number: 3,
+            column: 5,
+        });
+        assert_eq!(frame.file_name, "/home/me/project/src/main.rs");
+
+        Ray::clear_path_mapping();
+
+        assert_eq!(paths::map("/app/src/main.rs"), "/app/src/main.rs");
     }
 }
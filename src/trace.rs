@@ -0,0 +1,176 @@
+// std::backtrace::Backtrace doesn't expose its frames on stable, but its Display output is stable
+// enough to parse:
+//
+//    3: my_crate::module::function
+//              at ./src/module.rs:12:5
+//
+// so we capture one, read the frames back out of it and throw away everything that isn't the
+// user's own code.
+
+use crate::message::RayTraceFrame;
+use crate::paths;
+use std::backtrace::Backtrace;
+use std::path::Path;
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct RayFrame {
+    pub function: String,
+    pub file: String,
+    pub line_number: u32,
+    pub column: u32,
+}
+
+impl RayFrame {
+    // `a::b::Type::method` splits into `a::b::Type` and `method`
+    pub fn class_and_method(&self) -> (String, String) {
+        let function = self.function.trim_end_matches("::{{closure}}");
+
+        match function.rsplit_once("::") {
+            Some((class, method)) => (class.to_string(), method.to_string()),
+            _ => ("".to_string(), function.to_string()),
+        }
+    }
+}
+
+impl From<RayFrame> for RayTraceFrame {
+    fn from(frame: RayFrame) -> Self {
+        let (class, method) = frame.class_and_method();
+
+        RayTraceFrame {
+            file_name: paths::map(&frame.file),
+            line_number: frame.line_number,
+            class,
+            method,
+            vendor_frame: false,
+        }
+    }
+}
+
+// Crates whose frames are never interesting to the person debugging
+const VENDOR_CRATES: [&str; 11] = [
+    "std",
+    "core",
+    "alloc",
+    "test",
+    "tokio",
+    "futures",
+    "futures_util",
+    "backtrace",
+    "anyhow",
+    "eyre",
+    "ray_rust",
+];
+
+// Captures the current stack, keeping only frames from the caller's code
+pub fn capture() -> Vec<RayFrame> {
+    user_frames(&Backtrace::force_capture().to_string())
+}
+
+// Reads the frames out of a `Backtrace`'s Display output, keeping only the user's code
+pub fn user_frames(backtrace: &str) -> Vec<RayFrame> {
+    parse(backtrace)
+        .into_iter()
+        .filter(|frame| !is_vendor_frame(frame))
+        .collect()
+}
+
+// Reads every frame out of a `Backtrace`'s Display output
+pub fn parse(backtrace: &str) -> Vec<RayFrame> {
+    let mut frames: Vec<RayFrame> = Vec::new();
+
+    for line in backtrace.lines() {
+        let line = line.trim();
+
+        if let Some(location) = line.strip_prefix("at ") {
+            if let Some(frame) = frames.last_mut() {
+                let (file, line_number, column) = parse_location(location);
+
+                frame.file = file;
+                frame.line_number = line_number;
+                frame.column = column;
+            }
+
+            continue;
+        }
+
+        let Some((index, function)) = line.split_once(": ") else {
+            continue;
+        };
+
+        if index.parse::<usize>().is_err() {
+            continue;
+        }
+
+        frames.push(RayFrame {
+            function: function.to_string(),
+            file: "".to_string(),
+            line_number: 0,
+            column: 0,
+        });
+    }
+
+    frames
+}
+
+pub fn is_vendor_frame(frame: &RayFrame) -> bool {
+    let function = frame
+        .function
+        .trim_start_matches(['<', '&'])
+        .trim_start_matches("dyn ")
+        .trim_start_matches("mut ");
+
+    // Our own tests live inside the crate, so they'd otherwise never see their own frames
+    if function.starts_with("ray_rust::tests::") {
+        return false;
+    }
+
+    // C runtime and unwinding frames like `main`, `_start` and `__libc_start_main`
+    let Some((krate, _)) = function.split_once("::") else {
+        return frame.file.is_empty() || function.starts_with("__") || function == "main";
+    };
+
+    VENDOR_CRATES.contains(&krate) || krate.starts_with("__") || frame.file.starts_with("/rustc/")
+}
+
+// `./src/lib.rs:12:5`, the column is optional and the path may contain colons on Windows
+fn parse_location(location: &str) -> (String, u32, u32) {
+    let mut parts = location.rsplitn(3, ':');
+
+    let last = parts.next().and_then(|part| part.parse::<u32>().ok());
+    let second = parts.next();
+    let rest = parts.next();
+
+    let (file, line_number, column) = match (rest, second, last) {
+        (Some(file), Some(line), Some(column)) if line.parse::<u32>().is_ok() => {
+            (file.to_string(), line.parse().unwrap_or(0), column)
+        }
+        (rest, Some(file), Some(line)) => {
+            let file = match rest {
+                Some(rest) => format!("{}:{}", rest, file),
+                None => file.to_string(),
+            };
+
+            (file, line, 0)
+        }
+        _ => (location.to_string(), 0, 0),
+    };
+
+    (absolute(&file), line_number, column)
+}
+
+// Ray needs absolute paths to open the file in an editor
+fn absolute(file: &str) -> String {
+    let path = Path::new(file);
+
+    if path.is_absolute() {
+        return file.to_string();
+    }
+
+    match std::env::current_dir() {
+        Ok(dir) => dir
+            .join(path.strip_prefix("./").unwrap_or(path))
+            .display()
+            .to_string(),
+        Err(_) => file.to_string(),
+    }
+}
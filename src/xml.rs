@@ -0,0 +1,363 @@
+// Helpers for Ray::xml: a small tokenizer that checks the document is well formed, then
+// re-indents it as syntax coloured HTML. It doesn't try to validate against a schema or resolve
+// entities, it only needs to be good enough to read SOAP envelopes and feeds.
+
+use crate::dumper::{escape_html, span};
+
+const INDENT: &str = "  ";
+
+const STYLE_TAG: &str = "color: #7c3aed;";
+const STYLE_ATTRIBUTE: &str = "color: #d97706;";
+const STYLE_VALUE: &str = "color: #16a34a;";
+const STYLE_COMMENT: &str = "color: #9ca3af; font-style: italic;";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct XmlError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    // <?xml ... ?>, <!DOCTYPE ...>, <![CDATA[...]]> and comments, kept as they were written
+    Raw { text: String, comment: bool },
+    Open { name: String, body: String },
+    Empty { name: String, body: String },
+    Close { name: String },
+    Text(String),
+}
+
+// Checks the XML is well formed and renders it re-indented and syntax coloured
+pub fn pretty_html(xml: &str) -> Result<String, XmlError> {
+    let tokens = tokenize(xml)?;
+    let mut html = String::new();
+    let mut depth = 0;
+    let mut i = 0;
+
+    while i < tokens.len() {
+        match &tokens[i] {
+            Token::Raw { text, comment } => {
+                line(&mut html, depth);
+                if *comment {
+                    span(&mut html, STYLE_COMMENT, text);
+                } else {
+                    html.push_str(&escape_html(text));
+                }
+            }
+            Token::Open { name, body } => {
+                line(&mut html, depth);
+                tag(&mut html, name, body, false);
+
+                // Elements that only hold text stay on one line: <name>text</name>
+                if let (Some(Token::Text(text)), Some(Token::Close { .. })) =
+                    (tokens.get(i + 1), tokens.get(i + 2))
+                {
+                    html.push_str(&escape_html(text));
+                    close(&mut html, name);
+                    i += 3;
+                    continue;
+                }
+
+                if let Some(Token::Close { .. }) = tokens.get(i + 1) {
+                    close(&mut html, name);
+                    i += 2;
+                    continue;
+                }
+
+                depth += 1;
+            }
+            Token::Empty { name, body } => {
+                line(&mut html, depth);
+                tag(&mut html, name, body, true);
+            }
+            Token::Close { name } => {
+                depth -= 1;
+                line(&mut html, depth);
+                close(&mut html, name);
+            }
+            Token::Text(text) => {
+                line(&mut html, depth);
+                html.push_str(&escape_html(text));
+            }
+        }
+
+        i += 1;
+    }
+
+    Ok(format!("<pre>{}</pre>", html.trim_start_matches('\n')))
+}
+
+fn tokenize(xml: &str) -> Result<Vec<Token>, XmlError> {
+    let chars: Vec<char> = xml.chars().collect();
+    let mut tokens = Vec::new();
+    let mut stack: Vec<(String, usize)> = Vec::new();
+    let mut seen_root = false;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let start = i;
+
+        if chars[i] != '<' {
+            while i < chars.len() && chars[i] != '<' {
+                i += 1;
+            }
+
+            let text: String = chars[start..i].iter().collect();
+            let text = text.trim();
+
+            if !text.is_empty() {
+                if stack.is_empty() {
+                    return Err(error(&chars, start, "text outside of the root element"));
+                }
+
+                tokens.push(Token::Text(text.to_string()));
+            }
+
+            continue;
+        }
+
+        let rest: String = chars[i..chars.len().min(i + 9)].iter().collect();
+
+        let (terminator, comment) = if rest.starts_with("<!--") {
+            ("-->", true)
+        } else if rest.starts_with("<![CDATA[") {
+            ("]]>", false)
+        } else if rest.starts_with("<?") {
+            ("?>", false)
+        } else if rest.starts_with("<!") {
+            (">", false)
+        } else {
+            ("", false)
+        };
+
+        if !terminator.is_empty() {
+            let end = find(&chars, i + 2, terminator)
+                .ok_or_else(|| error(&chars, start, "unterminated markup"))?;
+
+            i = end + terminator.len();
+
+            let text: String = chars[start..i].iter().collect();
+
+            if text.starts_with("<![CDATA[") {
+                if stack.is_empty() {
+                    return Err(error(&chars, start, "CDATA outside of the root element"));
+                }
+
+                tokens.push(Token::Text(text));
+            } else {
+                tokens.push(Token::Raw { text, comment });
+            }
+
+            continue;
+        }
+
+        // A tag, find its end while skipping over quoted attribute values
+        let mut quote = None;
+        i += 1;
+
+        while i < chars.len() {
+            match (quote, chars[i]) {
+                (None, '"' | '\'') => quote = Some(chars[i]),
+                (Some(q), c) if c == q => quote = None,
+                (None, '<') => return Err(error(&chars, i, "unexpected '<' inside a tag")),
+                (None, '>') => break,
+                _ => {}
+            }
+
+            i += 1;
+        }
+
+        if i >= chars.len() {
+            return Err(error(&chars, start, "unterminated tag"));
+        }
+
+        let inner: String = chars[start + 1..i].iter().collect();
+        i += 1;
+
+        if let Some(name) = inner.strip_prefix('/') {
+            let name = name.trim().to_string();
+
+            match stack.pop() {
+                Some((open, _)) if open == name => tokens.push(Token::Close { name }),
+                Some((open, _)) => {
+                    return Err(error(
+                        &chars,
+                        start,
+                        &format!("expected </{}> but found </{}>", open, name),
+                    ))
+                }
+                None => {
+                    return Err(error(
+                        &chars,
+                        start,
+                        &format!("closing tag </{}> was never opened", name),
+                    ))
+                }
+            }
+
+            continue;
+        }
+
+        let self_closing = inner.ends_with('/');
+        let inner = inner.trim_end_matches('/');
+        let name_end = inner
+            .find(|c: char| c.is_whitespace())
+            .unwrap_or(inner.len());
+        let name = inner[..name_end].to_string();
+        let body = inner[name_end..].trim().to_string();
+
+        if name.is_empty() {
+            return Err(error(&chars, start, "missing tag name"));
+        }
+
+        check_attributes(&body).map_err(|message| error(&chars, start, &message))?;
+
+        if stack.is_empty() {
+            if seen_root {
+                return Err(error(&chars, start, "more than one root element"));
+            }
+
+            seen_root = true;
+        }
+
+        if self_closing {
+            tokens.push(Token::Empty { name, body });
+        } else {
+            stack.push((name.clone(), start));
+            tokens.push(Token::Open { name, body });
+        }
+    }
+
+    if let Some((name, start)) = stack.pop() {
+        return Err(error(&chars, start, &format!("<{}> is never closed", name)));
+    }
+
+    if !seen_root {
+        return Err(error(&chars, chars.len(), "no root element"));
+    }
+
+    Ok(tokens)
+}
+
+// Every attribute needs to look like name="value" or name='value'
+fn check_attributes(body: &str) -> Result<(), String> {
+    for (name, value) in attributes(body) {
+        if name.is_empty() {
+            return Err("attribute without a name".to_string());
+        }
+
+        match value {
+            Some(value)
+                if value.len() >= 2 && (value.starts_with('"') || value.starts_with('\'')) => {}
+            _ => return Err(format!("attribute {} needs a quoted value", name)),
+        }
+    }
+
+    Ok(())
+}
+
+// Splits `a="1" b='2'` into names and quoted values, keeping the quotes
+fn attributes(body: &str) -> Vec<(String, Option<String>)> {
+    let chars: Vec<char> = body.chars().collect();
+    let mut attributes = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        while i < chars.len() && chars[i].is_whitespace() {
+            i += 1;
+        }
+
+        if i >= chars.len() {
+            break;
+        }
+
+        let start = i;
+
+        while i < chars.len() && chars[i] != '=' && !chars[i].is_whitespace() {
+            i += 1;
+        }
+
+        let name: String = chars[start..i].iter().collect();
+
+        while i < chars.len() && chars[i].is_whitespace() {
+            i += 1;
+        }
+
+        if i >= chars.len() || chars[i] != '=' {
+            attributes.push((name, None));
+            continue;
+        }
+
+        i += 1;
+
+        while i < chars.len() && chars[i].is_whitespace() {
+            i += 1;
+        }
+
+        let start = i;
+
+        if i < chars.len() && (chars[i] == '"' || chars[i] == '\'') {
+            let quote = chars[i];
+            i += 1;
+
+            while i < chars.len() && chars[i] != quote {
+                i += 1;
+            }
+
+            i = (i + 1).min(chars.len());
+        } else {
+            while i < chars.len() && !chars[i].is_whitespace() {
+                i += 1;
+            }
+        }
+
+        attributes.push((name, Some(chars[start..i].iter().collect())));
+    }
+
+    attributes
+}
+
+fn find(chars: &[char], from: usize, needle: &str) -> Option<usize> {
+    let needle: Vec<char> = needle.chars().collect();
+
+    (from..chars.len()).find(|&i| chars[i..].starts_with(&needle))
+}
+
+// Lines and columns start at 1, like every editor
+fn error(chars: &[char], position: usize, message: &str) -> XmlError {
+    let before = &chars[..position.min(chars.len())];
+    let line = before.iter().filter(|&&c| c == '\n').count() + 1;
+    let column = before.iter().rev().take_while(|&&c| c != '\n').count() + 1;
+
+    XmlError {
+        line,
+        column,
+        message: message.to_string(),
+    }
+}
+
+fn line(html: &mut String, depth: usize) {
+    html.push('\n');
+    html.push_str(&INDENT.repeat(depth));
+}
+
+fn tag(html: &mut String, name: &str, body: &str, self_closing: bool) {
+    span(html, STYLE_TAG, &format!("<{}", name));
+
+    for (attribute, value) in attributes(body) {
+        html.push(' ');
+        span(html, STYLE_ATTRIBUTE, &attribute);
+
+        if let Some(value) = value {
+            html.push('=');
+            span(html, STYLE_VALUE, &value);
+        }
+    }
+
+    span(html, STYLE_TAG, if self_closing { " />" } else { ">" });
+}
+
+fn close(html: &mut String, name: &str) {
+    span(html, STYLE_TAG, &format!("</{}>", name));
+}